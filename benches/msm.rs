@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use elliptic_curve::{Field, Group};
+use elliptic_curves::{
+    msm::{msm, pippenger, straus},
+    schnorr::{verify_equations, SchnorrGroup},
+    KeyPair,
+};
+use k256::{ProjectivePoint, Scalar};
+
+fn random_terms(n: usize) -> Vec<(Scalar, ProjectivePoint)> {
+    let mut rng = rand::thread_rng();
+    (0..n)
+        .map(|_| (Scalar::random(&mut rng), ProjectivePoint::random(&mut rng)))
+        .collect()
+}
+
+/// Compare separate scalar multiplications against Straus and Pippenger for growing numbers of terms
+fn bench_msm(c: &mut Criterion) {
+    let mut group = c.benchmark_group("msm");
+    for n in [2, 8, 32, 64, 128, 512, 2048] {
+        let terms = random_terms(n);
+        group.bench_with_input(BenchmarkId::new("naive", n), &terms, |b, terms| {
+            b.iter(|| {
+                terms
+                    .iter()
+                    .fold(ProjectivePoint::IDENTITY, |acc, (s, p)| acc + p * s)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("straus", n), &terms, |b, terms| {
+            b.iter(|| straus(terms))
+        });
+        group.bench_with_input(BenchmarkId::new("pippenger", n), &terms, |b, terms| {
+            b.iter(|| pippenger(terms))
+        });
+    }
+    group.finish();
+}
+
+/// Verifying one Schnorr signature, and many at once, which now go through `msm`
+fn bench_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("schnorr_verify");
+    let signed: Vec<_> = (0..128)
+        .map(|i| {
+            let key_pair = KeyPair::<ProjectivePoint>::random();
+            let message = format!("message {}", i);
+            let signature =
+                <ProjectivePoint as SchnorrGroup>::sign(key_pair.secret_key(), &message).unwrap();
+            (signature, key_pair.public_key(), message)
+        })
+        .collect();
+
+    let (signature, pk, message) = &signed[0];
+    group.bench_function("single", |b| {
+        b.iter(|| <ProjectivePoint as SchnorrGroup>::verify(*signature, *pk, message))
+    });
+
+    let equations: Vec<_> = signed
+        .iter()
+        .map(|(signature, pk, message)| {
+            <ProjectivePoint as SchnorrGroup>::equation(*signature, *pk, message)
+        })
+        .collect();
+    group.bench_function("one_by_one_128", |b| {
+        b.iter(|| equations.iter().all(|equation| equation.holds()))
+    });
+    group.bench_function("batch_128", |b| b.iter(|| verify_equations(&equations)));
+    group.finish();
+}
+
+/// The default dispatch of `msm` around its Straus/Pippenger threshold
+fn bench_threshold(c: &mut Criterion) {
+    let mut group = c.benchmark_group("msm_dispatch");
+    for n in [128, 256, 384, 512] {
+        let terms = random_terms(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &terms, |b, terms| {
+            b.iter(|| msm(terms))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_msm, bench_verify, bench_threshold);
+criterion_main!(benches);
@@ -0,0 +1,293 @@
+use k256::ProjectivePoint;
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{
+    ecdsa::CurveGroup, hash_to_scalar, modulus_bytes, msm::msm, scalar_from_bytes, scalar_to_bytes,
+    Error, PublicKey, SecretKey,
+};
+
+// Schnorr signatures as specified for Bitcoin in BIP-340
+// https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+// BIP-340 is defined over secp256k1 (k256::ProjectivePoint), where points, x coordinates and scalars are all 32 bytes.
+
+/// Requires the functions to create and verify BIP-340 signatures
+pub trait BIP340Group: CurveGroup {
+    /// Create a BIP-340 signature over a message of any length, using fresh auxiliary randomness
+    fn sign(secret_key: &SecretKey<Self>, message: &[u8]) -> Result<Signature<Self>, Error>;
+
+    /// Like `sign`, drawing the auxiliary randomness from the given RNG
+    fn sign_with_rng(
+        secret_key: &SecretKey<Self>,
+        message: &[u8],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Signature<Self>, Error>;
+
+    /// Create a BIP-340 signature with the given 32 bytes of auxiliary randomness. The same inputs always give the same signature.
+    fn sign_with_aux_rand(
+        secret_key: &SecretKey<Self>,
+        message: &[u8],
+        aux_rand: &[u8; 32],
+    ) -> Result<Signature<Self>, Error>;
+
+    /// Verify a BIP-340 signature against an x-only public key
+    fn verify(
+        signature: &Signature<Self>,
+        message: &[u8],
+        public_key: &XOnlyPublicKey<Self>,
+    ) -> Result<(), Error>;
+}
+
+/// A public key encoded as only its x coordinate; the point is the one with that x and an even y coordinate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XOnlyPublicKey<G: CurveGroup> {
+    point: G,
+}
+
+impl<G: CurveGroup> XOnlyPublicKey<G> {
+    /// Decode the 32 byte x coordinate, failing with `Error::InvalidEncoding` if it is not the x coordinate of a point on the curve
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let point = G::from_x(bytes, false).ok_or(Error::InvalidEncoding)?;
+        Ok(XOnlyPublicKey { point })
+    }
+
+    /// Encode as the 32 byte x coordinate
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.point.x()
+    }
+
+    /// Returns the point with even y that this key represents
+    pub fn point(&self) -> G {
+        self.point
+    }
+}
+
+impl<G: CurveGroup> From<PublicKey<G>> for XOnlyPublicKey<G> {
+    /// Drop the y coordinate of a public key, negating the point if its y is odd
+    fn from(public_key: PublicKey<G>) -> Self {
+        XOnlyPublicKey {
+            point: with_even_y(public_key.point()).0,
+        }
+    }
+}
+
+/// A 64 byte BIP-340 signature: the x coordinate of R followed by s
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature<G: CurveGroup> {
+    r: Vec<u8>,
+    s: G::Scalar,
+}
+
+impl<G: CurveGroup> Signature<G> {
+    /// Create a signature from the x coordinate of R and s, e.g. for a MuSig signature assembled from partial signatures
+    pub(crate) fn from_parts(r: Vec<u8>, s: G::Scalar) -> Self {
+        Signature { r, s }
+    }
+
+    /// Returns the x coordinate of R as big-endian bytes
+    pub fn r(&self) -> &[u8] {
+        &self.r
+    }
+
+    /// Returns the s value of a signature
+    pub fn s(&self) -> G::Scalar {
+        self.s
+    }
+
+    /// Encode as r || s
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.r.clone();
+        bytes.extend_from_slice(&scalar_to_bytes(&self.s));
+        bytes
+    }
+
+    /// Decode r || s, failing with `Error::InvalidEncoding` if the length is wrong or s is not less than the group order.
+    /// r is only checked when verifying, where an r that is not a valid x coordinate never matches.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let len = modulus_bytes::<G::Scalar>().len();
+        if bytes.len() != 2 * len {
+            return Err(Error::InvalidEncoding);
+        }
+        let (r, s) = bytes.split_at(len);
+        Ok(Signature {
+            r: r.to_vec(),
+            s: scalar_from_bytes(s)?,
+        })
+    }
+}
+
+/// BIP-340 is only defined over secp256k1
+impl BIP340Group for ProjectivePoint {
+    fn sign(sk: &SecretKey<Self>, message: &[u8]) -> Result<Signature<Self>, Error> {
+        <Self as BIP340Group>::sign_with_rng(sk, message, &mut rand::thread_rng())
+    }
+
+    fn sign_with_rng(
+        sk: &SecretKey<Self>,
+        message: &[u8],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Signature<Self>, Error> {
+        let mut aux_rand = [0u8; 32];
+        rng.fill_bytes(&mut aux_rand);
+        <Self as BIP340Group>::sign_with_aux_rand(sk, message, &aux_rand)
+    }
+
+    fn sign_with_aux_rand(
+        sk: &SecretKey<Self>,
+        message: &[u8],
+        aux_rand: &[u8; 32],
+    ) -> Result<Signature<Self>, Error> {
+        // Sign with whichever of d and n - d has a public key with even y
+        let (pk, negated) = with_even_y(sk.public_key().point());
+        let d = if negated {
+            SecretKey::<Self>::from_scalar(Self::Scalar::ZERO - sk.scalar())?
+        } else {
+            sk.clone()
+        };
+        let pk_bytes = pk.x();
+
+        // The nonce is derived from the key masked with the auxiliary randomness, the public key and the message
+        let t: Zeroizing<Vec<u8>> = Zeroizing::new(
+            Zeroizing::new(scalar_to_bytes(d.scalar()))
+                .iter()
+                .zip(tagged_hash("BIP0340/aux", &[aux_rand]))
+                .map(|(d_byte, aux_byte)| d_byte ^ aux_byte)
+                .collect(),
+        );
+        let k = hash_to_scalar::<Self::Scalar>(&tagged_hash(
+            "BIP0340/nonce",
+            &[&t, &pk_bytes, message],
+        ));
+        if bool::from(k.is_zero()) {
+            return Err(Error::ZeroScalar);
+        }
+        let (r_point, negated) = with_even_y(Self::GENERATOR * k);
+        let k = if negated { -k } else { k };
+        let r = r_point.x();
+
+        let e = challenge::<Self>(&r, &pk_bytes, message);
+        Ok(Signature {
+            r,
+            s: k + e * d.scalar(),
+        })
+    }
+
+    fn verify(
+        signature: &Signature<Self>,
+        message: &[u8],
+        public_key: &XOnlyPublicKey<Self>,
+    ) -> Result<(), Error> {
+        let pk = public_key.point();
+        let e = challenge::<Self>(&signature.r, &pk.x(), message);
+        // R = s * G - e * P must have even y and x coordinate r
+        let r_point = msm(&[(signature.s, Self::GENERATOR), (-e, pk)]);
+        if r_point == Self::IDENTITY {
+            return Err(Error::IdentityPoint);
+        }
+        if r_point.y_is_odd() || r_point.x() != signature.r {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the point or its negation, whichever has an even y coordinate, and whether it was negated
+pub(crate) fn with_even_y<G: CurveGroup>(point: G) -> (G, bool) {
+    if point.y_is_odd() {
+        (-point, true)
+    } else {
+        (point, false)
+    }
+}
+
+/// The challenge e = hash_BIP0340/challenge(r || pk || m) mod n
+fn challenge<G: CurveGroup>(r: &[u8], pk: &[u8], message: &[u8]) -> G::Scalar {
+    hash_to_scalar::<G::Scalar>(&tagged_hash("BIP0340/challenge", &[r, pk, message]))
+}
+
+/// The BIP-340 tagged hash SHA256(SHA256(tag) || SHA256(tag) || inputs), which keeps hashes for different purposes apart
+pub fn tagged_hash(tag: &str, inputs: &[&[u8]]) -> [u8; 32] {
+    crate::tagged_hash::<Sha256>(tag, inputs).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bytes_from_hex;
+
+    /// Runs every row of the official BIP-340 test vector CSV: rows with a secret key are signed and compared byte for byte, and all rows are verified
+    #[test]
+    fn bip340_test_vectors() {
+        let csv = include_str!("../test-vectors/bip340.csv");
+        for line in csv.lines().skip(1) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let (index, sk, pk, aux_rand, message, signature, valid) = (
+                fields[0],
+                fields[1],
+                bytes_from_hex(fields[2]),
+                bytes_from_hex(fields[3]),
+                bytes_from_hex(fields[4]),
+                bytes_from_hex(fields[5]),
+                fields[6] == "TRUE",
+            );
+
+            if !sk.is_empty() {
+                let sk =
+                    SecretKey::<ProjectivePoint>::from_scalar(hash_to_scalar(&bytes_from_hex(sk)))
+                        .unwrap();
+                assert_eq!(
+                    XOnlyPublicKey::from(sk.public_key()).to_bytes(),
+                    pk,
+                    "wrong public key for index {}",
+                    index
+                );
+                let aux_rand: [u8; 32] = aux_rand.try_into().unwrap();
+                let signed =
+                    <ProjectivePoint as BIP340Group>::sign_with_aux_rand(&sk, &message, &aux_rand)
+                        .unwrap();
+                assert_eq!(
+                    signed.to_bytes(),
+                    signature,
+                    "wrong signature for index {}",
+                    index
+                );
+            }
+
+            let verified = match (
+                XOnlyPublicKey::<ProjectivePoint>::from_bytes(&pk),
+                Signature::from_bytes(&signature),
+            ) {
+                (Ok(pk), Ok(signature)) => {
+                    <ProjectivePoint as BIP340Group>::verify(&signature, &message, &pk).is_ok()
+                }
+                _ => false,
+            };
+            assert_eq!(
+                verified, valid,
+                "wrong verification result for index {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn bip340_test_true() {
+        for _ in 1..100 {
+            let key_pair = crate::KeyPair::<ProjectivePoint>::random();
+            let mut message = [0u8; 40];
+            rand::thread_rng().fill_bytes(&mut message);
+            let signature =
+                <ProjectivePoint as BIP340Group>::sign(key_pair.secret_key(), &message).unwrap();
+            let pk = XOnlyPublicKey::from(key_pair.public_key());
+            assert_eq!(signature.to_bytes().len(), 64);
+            assert!(<ProjectivePoint as BIP340Group>::verify(&signature, &message, &pk).is_ok());
+
+            message[0] ^= 1;
+            assert_eq!(
+                <ProjectivePoint as BIP340Group>::verify(&signature, &message, &pk),
+                Err(Error::InvalidSignature)
+            );
+        }
+    }
+}
@@ -1,44 +1,140 @@
-use elliptic_curve::{Field, Group, PrimeField};
+use elliptic_curve::Group;
+use hkdf::SimpleHkdf;
+use sha2::{digest::core_api::BlockSizeUser, Digest, Sha256};
+use zeroize::Zeroizing;
 
-// Required functions for ECDH (elliptic curve Diffie Hellman exchange)
-pub trait ECDHGroup {
-    type Scalar: PrimeField;
-
-    fn generate_private_key() -> Self::Scalar;
+use crate::{ecdsa::CurveGroup, Error, PublicKey, SecretKey};
 
-    fn generate_public_key(sk: Self::Scalar) -> Self;
-
-    fn generate_secret(sk: Self::Scalar, pk: Self) -> Self;
+// Required functions for ECDH (elliptic curve Diffie Hellman exchange)
+// Keys are the shared `SecretKey` and `PublicKey` types, e.g. from `KeyPair::random()`
+pub trait ECDHGroup: Group {
+    fn generate_secret(sk: &SecretKey<Self>, pk: PublicKey<Self>) -> Self;
 }
 
 impl<T: Group> ECDHGroup for T {
-    type Scalar = T::Scalar;
-
-    // Private key is a random scalar
-    fn generate_private_key() -> Self::Scalar {
-        let rng = rand::thread_rng();
-        <Self::Scalar as Field>::random(rng)
-    }
-    // Pk = sk * generator (in this case an elliptic curve point)
-    fn generate_public_key(sk: Self::Scalar) -> Self {
-        Self::generator() * sk
-    }
-
     // If parties A and B both have private and public keys, they can generate a shared secret as follows:
     // Secret of A: pk_b * sk_a
     // Secret of B: pk_a * sk_b
     // pk_b = sk_b * generator
     // pk_a = sk_a * generator
     // Therefore, both secrets equal sk_b * sk_a * generator and are equal
-    fn generate_secret(sk: Self::Scalar, pk: Self) -> Self {
-        pk * sk
+    fn generate_secret(sk: &SecretKey<Self>, pk: PublicKey<Self>) -> Self {
+        pk.point() * sk.scalar()
+    }
+}
+
+/// Selects the key derivation function that turns an ECDH shared secret Z into key bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf<'a> {
+    /// HKDF (RFC 5869) with a salt and a context string; an empty salt is the same as no salt
+    Hkdf { salt: &'a [u8], info: &'a [u8] },
+    /// The ANSI X9.63 KDF (SEC 1 section 3.6.1): H(Z || counter || SharedInfo) for counter = 1, 2, ...
+    X963 { shared_info: &'a [u8] },
+    /// The one-step KDF of NIST SP 800-56C with a hash function: H(counter || Z || FixedInfo) for counter = 1, 2, ...
+    OneStep { fixed_info: &'a [u8] },
+}
+
+impl Kdf<'_> {
+    /// Derive `length` bytes from the shared secret z with the hash function D.
+    /// Fails with `Error::InvalidKeyLength` if HKDF is asked for more than 255 hash outputs, or X9.63 and the one-step KDF for more than 2^32 - 1 of them.
+    pub fn derive<D: Digest + BlockSizeUser + Clone>(
+        &self,
+        z: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let max_blocks = match self {
+            Kdf::Hkdf { .. } => 255,
+            Kdf::X963 { .. } | Kdf::OneStep { .. } => u32::MAX as usize,
+        };
+        if length > max_blocks.saturating_mul(<D as Digest>::output_size()) {
+            return Err(Error::InvalidKeyLength);
+        }
+
+        let mut key = vec![0u8; length];
+        match *self {
+            Kdf::Hkdf { salt, info } => SimpleHkdf::<D>::new(Some(salt), z)
+                .expand(info, &mut key)
+                .expect("length is within the HKDF limit"),
+            Kdf::X963 { shared_info } => counter_kdf(&mut key, |counter| {
+                D::new()
+                    .chain_update(z)
+                    .chain_update(counter)
+                    .chain_update(shared_info)
+            }),
+            Kdf::OneStep { fixed_info } => counter_kdf(&mut key, |counter| {
+                D::new()
+                    .chain_update(counter)
+                    .chain_update(z)
+                    .chain_update(fixed_info)
+            }),
+        }
+        Ok(key)
+    }
+}
+
+/// Fill `key` with the hashes from `hash(counter)` for counter = 1, 2, ... as a 32-bit big-endian integer
+fn counter_kdf<D: Digest>(key: &mut [u8], hash: impl Fn([u8; 4]) -> D) {
+    for (block, counter) in key.chunks_mut(<D as Digest>::output_size()).zip(1u32..) {
+        block.copy_from_slice(&hash(counter.to_be_bytes()).finalize()[..block.len()]);
+    }
+}
+
+/// Key derivation from ECDH shared secrets, compatible with other ECDH implementations.
+/// D is the hash function of the KDF, SHA-256 unless another is given.
+pub trait ECDHKdfGroup<D: Digest = Sha256>: CurveGroup {
+    /// The shared secret Z in SEC 1 form (section 3.3.1): the big-endian x coordinate of the shared point
+    fn shared_secret(sk: &SecretKey<Self>, pk: PublicKey<Self>) -> Zeroizing<Vec<u8>>;
+
+    /// Derive `length` bytes of key material from the shared secret with HKDF, using the caller's salt and context string `info`
+    fn derive_key(
+        sk: &SecretKey<Self>,
+        pk: PublicKey<Self>,
+        salt: &[u8],
+        info: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, Error>;
+
+    /// Derive `length` bytes of key material from the shared secret with the given key derivation function
+    fn derive_key_with_kdf(
+        sk: &SecretKey<Self>,
+        pk: PublicKey<Self>,
+        kdf: Kdf,
+        length: usize,
+    ) -> Result<Vec<u8>, Error>;
+}
+
+impl<T: CurveGroup, D: Digest + BlockSizeUser + Clone> ECDHKdfGroup<D> for T {
+    fn shared_secret(sk: &SecretKey<Self>, pk: PublicKey<Self>) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(Self::generate_secret(sk, pk).x())
+    }
+
+    fn derive_key(
+        sk: &SecretKey<Self>,
+        pk: PublicKey<Self>,
+        salt: &[u8],
+        info: &[u8],
+        length: usize,
+    ) -> Result<Vec<u8>, Error> {
+        <Self as ECDHKdfGroup<D>>::derive_key_with_kdf(sk, pk, Kdf::Hkdf { salt, info }, length)
+    }
+
+    fn derive_key_with_kdf(
+        sk: &SecretKey<Self>,
+        pk: PublicKey<Self>,
+        kdf: Kdf,
+        length: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let z = <Self as ECDHKdfGroup<D>>::shared_secret(sk, pk);
+        kdf.derive::<D>(&z, length)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{bytes_from_hex, KeyPair};
     use k256::ProjectivePoint;
+    use sha2::Sha384;
 
     #[test]
     fn ecdh_test() {
@@ -49,15 +145,154 @@ mod test {
 
     // Generate a two random signers and create a shared secret, then check that the shared secrets are equal.
     fn ecdh_test_aux() {
-        let sk_a = ProjectivePoint::generate_private_key();
-        let pk_a = ProjectivePoint::generate_public_key(sk_a);
-
-        let sk_b = ProjectivePoint::generate_private_key();
-        let pk_b = ProjectivePoint::generate_public_key(sk_b);
+        let key_pair_a = KeyPair::<ProjectivePoint>::random();
+        let key_pair_b = KeyPair::<ProjectivePoint>::random();
 
-        let secret_a = ProjectivePoint::generate_secret(sk_a, pk_b);
-        let secret_b = ProjectivePoint::generate_secret(sk_b, pk_a);
+        let secret_a =
+            ProjectivePoint::generate_secret(key_pair_a.secret_key(), key_pair_b.public_key());
+        let secret_b =
+            ProjectivePoint::generate_secret(key_pair_b.secret_key(), key_pair_a.public_key());
 
         assert_eq!(secret_a, secret_b);
     }
+
+    /// Test the KDFs against RFC 5869 test case 1 and against X9.63 and SP 800-56C outputs from another implementation
+    #[test]
+    fn ecdh_test_kdf_vectors() {
+        let okm = Kdf::Hkdf {
+            salt: &bytes_from_hex("000102030405060708090a0b0c"),
+            info: &bytes_from_hex("f0f1f2f3f4f5f6f7f8f9"),
+        }
+        .derive::<Sha256>(&[0x0b; 22], 42);
+        assert_eq!(
+            okm,
+            Ok(bytes_from_hex(
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+            ))
+        );
+
+        let z: Vec<u8> = (0..32).collect();
+        let key = Kdf::X963 {
+            shared_info: b"shared info",
+        }
+        .derive::<Sha256>(&z, 48);
+        assert_eq!(
+            key,
+            Ok(bytes_from_hex("a2ba25392f91013464ed530b7260876d7d85630fd24cd7894a49600ab4c7a8c33354555992b455ebe41e722867504954"))
+        );
+        let key = Kdf::OneStep {
+            fixed_info: b"fixed info",
+        }
+        .derive::<Sha256>(&z, 48);
+        assert_eq!(
+            key,
+            Ok(bytes_from_hex("e729b56d8e881f035efe5c370189c8b06248661a64fe9676dd776bc34f79c5cec2b2950b318aaa472a6cf12da550d9a1"))
+        );
+    }
+
+    /// Test that both parties derive the same key with every KDF, and that the salt, context and KDF all change it
+    #[test]
+    fn ecdh_test_derive_key() {
+        let key_pair_a = KeyPair::<ProjectivePoint>::random();
+        let key_pair_b = KeyPair::<ProjectivePoint>::random();
+
+        let z = <ProjectivePoint as ECDHKdfGroup>::shared_secret(
+            key_pair_a.secret_key(),
+            key_pair_b.public_key(),
+        );
+        assert_eq!(z.len(), 32);
+        assert_eq!(
+            *z,
+            ProjectivePoint::generate_secret(key_pair_b.secret_key(), key_pair_a.public_key()).x()
+        );
+
+        let kdfs = [
+            Kdf::Hkdf {
+                salt: b"salt",
+                info: b"info",
+            },
+            Kdf::Hkdf {
+                salt: b"other salt",
+                info: b"info",
+            },
+            Kdf::Hkdf {
+                salt: b"salt",
+                info: b"other info",
+            },
+            Kdf::X963 {
+                shared_info: b"info",
+            },
+            Kdf::OneStep {
+                fixed_info: b"info",
+            },
+        ];
+        let mut keys = Vec::new();
+        for kdf in kdfs {
+            let key_a = <ProjectivePoint as ECDHKdfGroup>::derive_key_with_kdf(
+                key_pair_a.secret_key(),
+                key_pair_b.public_key(),
+                kdf,
+                64,
+            )
+            .unwrap();
+            let key_b = <ProjectivePoint as ECDHKdfGroup>::derive_key_with_kdf(
+                key_pair_b.secret_key(),
+                key_pair_a.public_key(),
+                kdf,
+                64,
+            )
+            .unwrap();
+            assert_eq!(key_a, key_b);
+            assert_eq!(key_a, kdf.derive::<Sha256>(&z, 64).unwrap());
+            assert!(!keys.contains(&key_a));
+            keys.push(key_a);
+        }
+
+        assert_eq!(
+            <ProjectivePoint as ECDHKdfGroup>::derive_key(
+                key_pair_a.secret_key(),
+                key_pair_b.public_key(),
+                b"salt",
+                b"info",
+                64
+            ),
+            Ok(keys[0].clone())
+        );
+        let key = <ProjectivePoint as ECDHKdfGroup<Sha384>>::derive_key(
+            key_pair_a.secret_key(),
+            key_pair_b.public_key(),
+            b"salt",
+            b"info",
+            64,
+        );
+        assert_eq!(key, kdfs[0].derive::<Sha384>(&z, 64));
+        assert_ne!(key, Ok(keys[0].clone()));
+    }
+
+    #[test]
+    fn ecdh_test_kdf_length() {
+        let z = [1u8; 32];
+        let hkdf = Kdf::Hkdf {
+            salt: &[],
+            info: &[],
+        };
+        assert_eq!(hkdf.derive::<Sha256>(&z, 0), Ok(Vec::new()));
+        assert_eq!(hkdf.derive::<Sha256>(&z, 255 * 32).unwrap().len(), 255 * 32);
+        assert_eq!(
+            hkdf.derive::<Sha256>(&z, 255 * 32 + 1),
+            Err(Error::InvalidKeyLength)
+        );
+        for kdf in [
+            Kdf::X963 { shared_info: &[] },
+            Kdf::OneStep { fixed_info: &[] },
+        ] {
+            // Keys that are not a whole number of hash outputs are the prefix of the longer key
+            let key = kdf.derive::<Sha256>(&z, 64).unwrap();
+            assert_eq!(kdf.derive::<Sha256>(&z, 33), Ok(key[..33].to_vec()));
+            assert_eq!(
+                kdf.derive::<Sha256>(&z, usize::MAX),
+                Err(Error::InvalidKeyLength)
+            );
+        }
+    }
 }
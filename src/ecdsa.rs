@@ -1,141 +1,524 @@
-use elliptic_curve::{group::GroupEncoding, Field, Group, PrimeField};
-use k256::ProjectivePoint;
-use sha2::{Digest, Sha256};
+use elliptic_curve::{
+    group::GroupEncoding,
+    point::{AffineCoordinates, DecompressPoint},
+    subtle::Choice,
+    Field, Group, PrimeField,
+};
+use k256::{AffinePoint, FieldBytes, ProjectivePoint};
+use rand::{CryptoRng, RngCore};
+use sha2::{digest::core_api::BlockSizeUser, Digest, Sha256};
 
-/// Requires the implementations of methods to create ECDSA signatures
-pub trait ECDSAGroup {
+use crate::{
+    hash_to_scalar, modulus_bytes, msm::msm, rfc6979, scalar_to_bytes, Error, PublicKey, SecretKey,
+};
 
-    /// Scalars modulo the order of the elliptic curve group
-    type Scalar: PrimeField;
+/// Selects how the nonce k is generated when signing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nonce {
+    /// k is a random scalar
+    Random,
+    /// k is derived from the secret key and the message hash as described in RFC 6979
+    Deterministic,
+    /// RFC 6979 with fresh randomness mixed in as additional data, so that a weak RNG alone cannot leak the key (RFC 6979 section 3.6)
+    Hedged,
+}
+
+/// Requires the implementations of methods to create ECDSA signatures.
+/// D is the hash function applied to messages and used for RFC 6979 nonces, SHA-256 unless another is given (e.g. SHA-384 for P-384 or Keccak-256 for Ethereum).
+/// Keys are the shared `SecretKey` and `PublicKey` types, e.g. from `KeyPair::random()`.
+pub trait ECDSAGroup<D: Digest = Sha256>: Group {
+    /// Create an ECDSA signature given a signer's secret key and a message to sign, using a random nonce
+    fn sign(secret_key: &SecretKey<Self>, message: &[u8])
+        -> Result<Signature<Self::Scalar>, Error>;
+
+    /// Create an ECDSA signature over a message digest that has already been computed with D, choosing how the nonce k is generated
+    /// and taking randomness for `Nonce::Random` and `Nonce::Hedged` from the given RNG. Signing the digest of a message gives the same
+    /// signature as signing the message. Also returns the recovery id v that lets `recover_public_key` rebuild the signer's public key:
+    /// bit 0 of v is the parity of the y coordinate of k * G and bit 1 is set if its x coordinate was reduced modulo n.
+    /// The other signing functions of this module are shorthands for this one.
+    fn sign_prehash_with(
+        secret_key: &SecretKey<Self>,
+        prehash: &[u8],
+        nonce: Nonce,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Signature<Self::Scalar>, u8), Error>;
+
+    /// Verify a message signed using ECDSA; returns an error describing why the signature is rejected if it is not valid
+    fn verify(
+        signature: Signature<Self::Scalar>,
+        message: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error>;
 
-    /// Generate random scalar that can be used as a private key
-    fn generate_private_key() -> Self::Scalar;
+    /// Verify an ECDSA signature given the digest of the message computed with D
+    fn verify_prehash(
+        signature: Signature<Self::Scalar>,
+        prehash: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error>;
 
-    /// Generate public key given secret/private key: pk = sk * generator
-    fn generate_public_key(sk: Self::Scalar) -> Self;
-    
-    /// Create an ECDSA signature given a signer's secret key and a message to sign
-    fn sign(secret_key: Self::Scalar, message: &[u8]) -> (Self::Scalar, Self::Scalar);
+    /// Verify an ECDSA signature given a hasher that the message has been streamed into
+    fn verify_digest(
+        signature: Signature<Self::Scalar>,
+        digest: D,
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error>;
 
-    /// Verify a message signed using ECDSA; returns true/false depending on if the signature is valid
-    fn verify(signature: (Self::Scalar, Self::Scalar), message: &[u8], public_key: Self) -> bool;
+    /// Verify a signature like `verify`, but also reject signatures with s > n/2 as BIP-62/BIP-146 require, so signatures cannot be malleated
+    fn verify_strict(
+        signature: Signature<Self::Scalar>,
+        message: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error>;
+
+    /// Verify a signature like `verify_prehash`, but also reject signatures with s > n/2 like `verify_strict`
+    fn verify_prehash_strict(
+        signature: Signature<Self::Scalar>,
+        prehash: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error>;
 }
 
-/// Provides methods to extract the x coordinate from an elliptic curve point and convert that coordinate into an element of the scalar field.
-pub trait CurveGroup: Group + GroupEncoding {
-    /// Function to extract the x coordinate of an elliptic curve point as bytes. This function must be implemented for any curve that you are using with this code (see below for an example with k256::ProjectivePoint)
-    fn x(&self) -> Vec<u8>;
+/// Create an ECDSA signature, choosing how the nonce k is generated
+pub fn sign_with_nonce<T: ECDSAGroup<D>, D: Digest>(
+    secret_key: &SecretKey<T>,
+    message: &[u8],
+    nonce: Nonce,
+) -> Result<Signature<T::Scalar>, Error> {
+    sign_prehash::<T, D>(secret_key, &D::digest(message), nonce)
+}
 
-    /// Take bytes of x coordinate and convert to a scalar
-    fn convert(&self) -> Self::Scalar {
-        let x_bytes = self.x();
+/// Create an ECDSA signature (r, s, v) where the recovery id v lets `recover_public_key` rebuild the signer's public key
+pub fn sign_recoverable<T: ECDSAGroup<D>, D: Digest>(
+    secret_key: &SecretKey<T>,
+    message: &[u8],
+    nonce: Nonce,
+) -> Result<(Signature<T::Scalar>, u8), Error> {
+    T::sign_prehash_with(
+        secret_key,
+        &D::digest(message),
+        nonce,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Create an ECDSA signature over a message digest that has already been computed with D, e.g. for a file too large to hold in memory
+pub fn sign_prehash<T: ECDSAGroup<D>, D: Digest>(
+    secret_key: &SecretKey<T>,
+    prehash: &[u8],
+    nonce: Nonce,
+) -> Result<Signature<T::Scalar>, Error> {
+    Ok(T::sign_prehash_with(secret_key, prehash, nonce, &mut rand::thread_rng())?.0)
+}
+
+/// Create an ECDSA signature from a hasher that the message has been streamed into
+pub fn sign_digest<T: ECDSAGroup<D>, D: Digest>(
+    secret_key: &SecretKey<T>,
+    digest: D,
+    nonce: Nonce,
+) -> Result<Signature<T::Scalar>, Error> {
+    sign_prehash::<T, D>(secret_key, &digest.finalize(), nonce)
+}
+
+/// Represents an ECDSA signature with r and s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature<F: PrimeField> {
+    r: F,
+    s: F,
+}
+
+impl<F: PrimeField> Signature<F> {
+    /// Create a signature from r and s
+    pub fn new(r: F, s: F) -> Self {
+        Signature { r, s }
+    }
+
+    /// Returns the r value of a signature
+    pub fn r(&self) -> F {
+        self.r
+    }
+
+    /// Returns the s value of a signature
+    pub fn s(&self) -> F {
+        self.s
+    }
+
+    /// Encode as the fixed width compact form r || s, each a big-endian integer of the scalar size (64 bytes for 256-bit curves)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = scalar_to_bytes(&self.r);
+        bytes.extend_from_slice(&scalar_to_bytes(&self.s));
+        bytes
+    }
 
-        let mut place = Self::Scalar::ONE;
-        let mut res = Self::Scalar::ZERO;
+    /// Decode the compact form r || s. Returns None if the length is wrong or r or s is not in [1, n - 1].
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let len = scalar_len::<F>();
+        if bytes.len() != 2 * len {
+            return None;
+        }
+        let (r, s) = bytes.split_at(len);
+        Some(Signature {
+            r: canonical_scalar(r)?,
+            s: canonical_scalar(s)?,
+        })
+    }
 
-        let mut buf = [0u8; 16];
+    /// Encode as an ASN.1 DER `Ecdsa-Sig-Value` (ANSI X9.62): SEQUENCE { r INTEGER, s INTEGER }
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut body = der_integer(&scalar_to_bytes(&self.r));
+        body.extend_from_slice(&der_integer(&scalar_to_bytes(&self.s)));
 
-        let mut factor = Self::Scalar::ONE;
-        for _ in 0..128 {
-            factor = factor.double();
+        let mut der = vec![0x30];
+        if body.len() >= 0x80 {
+            // Long form length; signatures are always shorter than 256 bytes
+            der.push(0x81);
         }
+        der.push(body.len() as u8);
+        der.extend_from_slice(&body);
+        der
+    }
 
-        for chunk in x_bytes.chunks(16) {
-            buf.copy_from_slice(chunk);
-            let num = u128::from_le_bytes(buf);
-            let scalar = Self::Scalar::from_u128(num);
-            res += place * scalar;
+    /// Decode a strict DER `Ecdsa-Sig-Value`. Returns None for anything that is not the unique DER encoding of a valid signature,
+    /// such as non-minimal lengths, extra zero padding, negative integers or trailing bytes.
+    pub fn from_der(bytes: &[u8]) -> Option<Self> {
+        let (&tag, rest) = bytes.split_first()?;
+        if tag != 0x30 {
+            return None;
+        }
+        let (&len, mut rest) = rest.split_first()?;
+        let len = match len {
+            0x81 => {
+                let (&len, tail) = rest.split_first()?;
+                rest = tail;
+                // The long form is only allowed for lengths that do not fit in the short form
+                if len < 0x80 {
+                    return None;
+                }
+                len
+            }
+            len if len < 0x80 => len,
+            _ => return None,
+        };
+        if rest.len() != len as usize {
+            return None;
+        }
 
-            place *= factor;
+        let (r, rest) = parse_der_integer(rest)?;
+        let (s, rest) = parse_der_integer(rest)?;
+        if !rest.is_empty() {
+            return None;
         }
-        res
+        Some(Signature {
+            r: canonical_scalar(r)?,
+            s: canonical_scalar(s)?,
+        })
+    }
+}
+
+/// Number of bytes in a big-endian encoding of a scalar
+fn scalar_len<F: PrimeField>() -> usize {
+    (F::NUM_BITS as usize).div_ceil(8)
+}
+
+/// Convert a big-endian integer to a scalar, returning None unless it is in [1, n - 1]
+fn canonical_scalar<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let len = scalar_len::<F>();
+    if bytes.len() > len {
+        return None;
+    }
+    let mut padded = vec![0u8; len - bytes.len()];
+    padded.extend_from_slice(bytes);
+    if padded >= modulus_bytes::<F>() || padded.iter().all(|byte| *byte == 0) {
+        return None;
+    }
+    Some(hash_to_scalar::<F>(&padded))
+}
+
+/// Encode a big-endian unsigned integer as a DER INTEGER: minimal length, with a zero byte in front if the top bit is set so it is not negative
+fn der_integer(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len() - 1);
+    let bytes = &bytes[start..];
+    let pad = bytes[0] & 0x80 != 0;
+
+    let mut der = vec![0x02, bytes.len() as u8 + pad as u8];
+    if pad {
+        der.push(0x00);
+    }
+    der.extend_from_slice(bytes);
+    der
+}
+
+/// Parse a DER INTEGER holding a positive value, returning its big-endian bytes without the sign padding and the remaining input
+fn parse_der_integer(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&tag, rest) = bytes.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    // Scalars are at most 66 bytes, so only the short form length is valid
+    if tag != 0x02 || len == 0 || len >= 0x80 || rest.len() < len as usize {
+        return None;
+    }
+    let (int, rest) = rest.split_at(len as usize);
+    // Negative
+    if int[0] & 0x80 != 0 {
+        return None;
+    }
+    if int[0] == 0x00 && len > 1 {
+        // A leading zero is only allowed when it is needed to keep the next byte positive
+        if int[1] & 0x80 == 0 {
+            return None;
+        }
+        return Some((&int[1..], rest));
+    }
+    Some((int, rest))
+}
+
+/// Returns true if s > n/2. Since n is odd, exactly one of s and n - s is in the upper half.
+pub fn is_high_s<F: PrimeField>(s: &F) -> bool {
+    scalar_to_bytes(s) > scalar_to_bytes(&-*s)
+}
+
+/// Returns whichever of s and n - s is at most n/2. Both (r, s) and (r, n - s) are valid signatures for the same message.
+pub fn normalize_s<F: PrimeField>(s: F) -> F {
+    if is_high_s(&s) {
+        -s
+    } else {
+        s
+    }
+}
+
+/// Provides methods to extract the x coordinate from an elliptic curve point and convert that coordinate into an element of the scalar field.
+pub trait CurveGroup: Group + GroupEncoding {
+    /// Function to extract the x coordinate of an elliptic curve point as big-endian bytes. This function must be implemented for any curve that you are using with this code (see below for an example with k256::ProjectivePoint)
+    fn x(&self) -> Vec<u8>;
+
+    /// Returns true if the y coordinate of the point is odd
+    fn y_is_odd(&self) -> bool;
+
+    /// Rebuild a point from its big-endian x coordinate and the parity of its y coordinate. Returns None if x is not the x coordinate of a point on the curve.
+    fn from_x(x: &[u8], y_is_odd: bool) -> Option<Self>;
+
+    /// Take bytes of x coordinate (big-endian) and convert to a scalar, reducing modulo the group order
+    fn convert(&self) -> Self::Scalar {
+        hash_to_scalar::<Self::Scalar>(&self.x())
     }
 }
 
 /// Hash a message to be signed and return a scalar.
-pub fn ecdsa_hash<T: Group>(input: &[u8]) -> T::Scalar {
-    let mut hasher = Sha256::new();
-    hasher.update(input);
-    let hash = hasher.finalize();
-    let mut factor = T::Scalar::ONE;
-    // Create a scalar with value 2^8
-    for _ in 0..8 {
-        factor = factor.double()
-    }
-    let mut scalar = <T::Scalar as Field>::ZERO;
-    // Construct a scalar from each byte
-    for byte in hash {
-        scalar *= factor;
-        scalar += <T::Scalar as From<u64>>::from(byte as u64)
-    }
-    scalar
-}
-
-impl<T: CurveGroup> ECDSAGroup for T {
-    type Scalar = T::Scalar;
-    fn generate_private_key() -> Self::Scalar {
-        let rng = rand::thread_rng();
-        <Self::Scalar as Field>::random(rng)
-    }
-
-    fn generate_public_key(sk: Self::Scalar) -> Self {
-        Self::generator() * sk
-    }
-
-    fn sign(sk: Self::Scalar, message: &[u8]) -> (Self::Scalar, Self::Scalar) {
-        // Need to take leftmost bits of z, todo (also in verifier)
-        let z: Self::Scalar = ecdsa_hash::<T>(message);
-        let mut rng = rand::thread_rng();
-        let k = <Self::Scalar as Field>::random(&mut rng);
-        // Check that k != 0
-        assert!(k.is_zero().unwrap_u8() == 0);
+pub fn ecdsa_hash<T: Group, D: Digest>(input: &[u8]) -> T::Scalar {
+    prehash_to_scalar::<T>(&D::digest(input))
+}
+
+/// Convert the digest of a message to a scalar.
+/// As in FIPS 186-5 and SEC1, z is the leftmost bits of the hash, as many as the bit length of the group order (bits2int), reduced modulo n.
+pub fn prehash_to_scalar<T: Group>(prehash: &[u8]) -> T::Scalar {
+    let z = rfc6979::bits2int(prehash, T::Scalar::NUM_BITS as usize);
+    hash_to_scalar::<T::Scalar>(&z)
+}
+
+impl<T: CurveGroup, D: Digest + BlockSizeUser> ECDSAGroup<D> for T {
+    fn sign(sk: &SecretKey<Self>, message: &[u8]) -> Result<Signature<Self::Scalar>, Error> {
+        sign_prehash::<Self, D>(sk, &D::digest(message), Nonce::Random)
+    }
+
+    fn sign_prehash_with(
+        sk: &SecretKey<Self>,
+        prehash: &[u8],
+        nonce: Nonce,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Signature<Self::Scalar>, u8), Error> {
+        let z: Self::Scalar = prehash_to_scalar::<T>(prehash);
+        let k = match nonce {
+            Nonce::Random => <Self::Scalar as Field>::random(&mut *rng),
+            Nonce::Deterministic => rfc6979::generate_k::<Self::Scalar, D>(sk.scalar(), &z, &[]),
+            Nonce::Hedged => {
+                let mut extra = [0u8; 32];
+                rng.fill_bytes(&mut extra);
+                rfc6979::generate_k::<Self::Scalar, D>(sk.scalar(), &z, &extra)
+            }
+        };
+        // k is zero with negligible probability, and then has no inverse
+        let k_inv = Option::<Self::Scalar>::from(k.invert()).ok_or(Error::ZeroScalar)?;
         let point = Self::generator() * k;
-        //let point_bytes = point.to_bytes();
         let r = point.convert();
-        //let r = <Self as CurveGroup>::convert(x_bytes);
-        let s = k.invert().unwrap() * (z + r * sk);
-        (r, s)
+        let s = k_inv * (z + r * sk.scalar());
+        // Neither r nor s may be zero, or the signature would not verify
+        if bool::from(r.is_zero()) || bool::from(s.is_zero()) {
+            return Err(Error::ZeroScalar);
+        }
+        // x overflowed if it was at least n, in which case r != x
+        let overflow = point.x() >= modulus_bytes::<Self::Scalar>();
+        let mut v = (point.y_is_odd() as u8) | ((overflow as u8) << 1);
+        // Always produce low-S signatures; negating s corresponds to signing with -k, which flips the parity of y
+        if is_high_s(&s) {
+            v ^= 1;
+        }
+        Ok((Signature::new(r, normalize_s(s)), v))
     }
 
-    fn verify(signature: (Self::Scalar, Self::Scalar), message: &[u8], public_key: Self) -> bool {
-        assert!(public_key != Self::identity());
-        let (r, s) = signature;
-        let z: Self::Scalar = ecdsa_hash::<T>(message);
-        let s_inv = s.invert().unwrap();
+    fn verify(
+        signature: Signature<Self::Scalar>,
+        message: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error> {
+        <Self as ECDSAGroup<D>>::verify_prehash(signature, &D::digest(message), public_key)
+    }
+
+    fn verify_digest(
+        signature: Signature<Self::Scalar>,
+        digest: D,
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error> {
+        <Self as ECDSAGroup<D>>::verify_prehash(signature, &digest.finalize(), public_key)
+    }
+
+    fn verify_prehash(
+        signature: Signature<Self::Scalar>,
+        prehash: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error> {
+        let (r, s) = (signature.r(), signature.s());
+        if bool::from(r.is_zero()) {
+            return Err(Error::ZeroScalar);
+        }
+        let s_inv = Option::<Self::Scalar>::from(s.invert()).ok_or(Error::ZeroScalar)?;
+        let z: Self::Scalar = prehash_to_scalar::<T>(prehash);
         let u1 = z * s_inv;
         let u2 = r * s_inv;
-        let point = Self::generator() * u1 + public_key * u2;
-        assert!(point != Self::identity());
-        point.convert() == r
+        let point = msm(&[(u1, Self::generator()), (u2, public_key.point())]);
+        if point == Self::identity() {
+            return Err(Error::IdentityPoint);
+        }
+        if point.convert() != r {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    fn verify_strict(
+        signature: Signature<Self::Scalar>,
+        message: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error> {
+        <Self as ECDSAGroup<D>>::verify_prehash_strict(signature, &D::digest(message), public_key)
+    }
+
+    fn verify_prehash_strict(
+        signature: Signature<Self::Scalar>,
+        prehash: &[u8],
+        public_key: PublicKey<Self>,
+    ) -> Result<(), Error> {
+        if is_high_s(&signature.s()) {
+            return Err(Error::HighS);
+        }
+        <Self as ECDSAGroup<D>>::verify_prehash(signature, prehash, public_key)
     }
 }
 
+/// Recover the public key that created an ECDSA signature with recovery id v over a message, like Ethereum's ecrecover.
+/// Returns None if no public key can be recovered. A recovered key still only means the signature verifies for that key.
+pub fn recover_public_key<T: CurveGroup, D: Digest>(
+    signature: (Signature<T::Scalar>, u8),
+    message: &[u8],
+) -> Option<PublicKey<T>> {
+    recover_public_key_prehash(signature, &D::digest(message))
+}
+
+/// Recover the public key that created an ECDSA signature with recovery id v, given the digest of the message
+pub fn recover_public_key_prehash<T: CurveGroup>(
+    signature: (Signature<T::Scalar>, u8),
+    prehash: &[u8],
+) -> Option<PublicKey<T>> {
+    let (r, s, v) = (signature.0.r(), signature.0.s(), signature.1);
+    if bool::from(r.is_zero()) || bool::from(s.is_zero()) || v > 3 {
+        return None;
+    }
+
+    // The x coordinate of R is r, or r + n if it was reduced when signing
+    let mut x = scalar_to_bytes(&r);
+    if v & 2 != 0 {
+        let modulus = modulus_bytes::<T::Scalar>();
+        let mut carry = 0u16;
+        for (x_byte, n_byte) in x.iter_mut().zip(modulus).rev() {
+            let sum = *x_byte as u16 + n_byte as u16 + carry;
+            *x_byte = sum as u8;
+            carry = sum >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    let r_point = T::from_x(&x, v & 1 != 0)?;
+
+    // s * R = z * G + r * pk, so pk = r^-1 * (s * R - z * G)
+    let z: T::Scalar = prehash_to_scalar::<T>(prehash);
+    let r_inv = r.invert().unwrap();
+    let public_key = (r_point * s - T::generator() * z) * r_inv;
+    PublicKey::from_point(public_key).ok()
+}
+
 impl CurveGroup for ProjectivePoint {
     fn x(&self) -> Vec<u8> {
-        let bytes = self.to_bytes();
+        self.to_affine().x().to_vec()
+    }
 
-        let bytes: &[u8] = bytes.as_ref();
+    fn y_is_odd(&self) -> bool {
+        self.to_affine().y_is_odd().into()
+    }
 
-        let size = 32;
+    fn from_x(x: &[u8], y_is_odd: bool) -> Option<Self> {
+        if x.len() != 32 {
+            return None;
+        }
+        let x = FieldBytes::clone_from_slice(x);
+        let point: Option<AffinePoint> =
+            AffinePoint::decompress(&x, Choice::from(y_is_odd as u8)).into();
+        point.map(Self::from)
+    }
+}
 
-        // NOTE: this is a hack that depends on the k256 implementation so probably should not be
-        // Curve points are stored as an x coordinate with a tag to indicate sign; the sign is the first byte and the x coordinate is the remaining 32 bytes.
-        assert_eq!(bytes.as_ref().len(), 1 + size);
-        let (start, end) = (1, 1 + size);
+/// Implement CurveGroup for the NIST curves from the RustCrypto crates, each behind the cargo feature of the same name.
+/// The unit tests always build them, as the curve crates are also dev-dependencies.
+#[cfg(any(feature = "p224", feature = "p256", feature = "p384", test))]
+macro_rules! impl_curve_group {
+    ($curve:ident) => {
+        impl CurveGroup for $curve::ProjectivePoint {
+            fn x(&self) -> Vec<u8> {
+                self.to_affine().x().to_vec()
+            }
 
-        let mut res = vec![0u8; size];
+            fn y_is_odd(&self) -> bool {
+                self.to_affine().y_is_odd().into()
+            }
 
-        res.copy_from_slice(&bytes[start..end]);
-        res
-    }
+            fn from_x(x: &[u8], y_is_odd: bool) -> Option<Self> {
+                let x = $curve::FieldBytes::from_exact_iter(x.iter().copied())?;
+                let point: Option<$curve::AffinePoint> =
+                    $curve::AffinePoint::decompress(&x, Choice::from(y_is_odd as u8)).into();
+                point.map(Self::from)
+            }
+        }
+    };
 }
 
+#[cfg(any(feature = "p224", test))]
+impl_curve_group!(p224);
+#[cfg(any(feature = "p256", test))]
+impl_curve_group!(p256);
+#[cfg(any(feature = "p384", test))]
+impl_curve_group!(p384);
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use k256::ProjectivePoint;
+    use crate::{bytes_from_hex, KeyPair};
+    use k256::{ProjectivePoint, Scalar};
     use rand::distributions::Alphanumeric;
-    use rand::Rng;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use sha2::{Sha384, Sha512};
+    use sha3::Keccak256;
 
     #[test]
     fn ecdsa_test_true() {
@@ -152,11 +535,10 @@ mod test {
     }
 
     /// Generates a random signer (public and private keys)
-    fn generate_random_signer() -> (<ProjectivePoint as Group>::Scalar, ProjectivePoint) {
-        let sk = ProjectivePoint::generate_private_key();
-        let pk = ProjectivePoint::generator() * sk;
+    fn generate_random_signer() -> (SecretKey<ProjectivePoint>, PublicKey<ProjectivePoint>) {
+        let key_pair = KeyPair::random();
 
-        (sk, pk)
+        (key_pair.secret_key().clone(), key_pair.public_key())
     }
 
     /// Generates a random string to use as a message in tests
@@ -166,8 +548,7 @@ mod test {
             .take(length)
             .map(char::from)
             .collect();
-        let message2 = message.into_iter().collect::<String>();
-        message2
+        message.into_iter().collect::<String>()
     }
 
     /// Tests whether a random message verifies correctly for a random signer
@@ -177,11 +558,495 @@ mod test {
         let message = get_random_message(10);
         let message_bytes = message.as_bytes();
 
-        let signature = ProjectivePoint::sign(sk, message_bytes.as_ref());
+        let signature = <ProjectivePoint as ECDSAGroup>::sign(&sk, message_bytes).unwrap();
+
+        let verifier = <ProjectivePoint as ECDSAGroup>::verify(signature, message_bytes, pk);
+
+        assert!(verifier.is_ok());
+    }
+
+    /// Parse a big-endian hex string into a scalar
+    fn scalar_from_hex<F: PrimeField>(hex: &str) -> F {
+        hash_to_scalar::<F>(&bytes_from_hex(hex))
+    }
+
+    /// Check deterministic signing against a vector of (secret key, message, r, s), where the signature is expected in low-S form
+    fn check_deterministic_vector<T: CurveGroup, D: Digest + BlockSizeUser>(
+        sk: &str,
+        message: &str,
+        r: &str,
+        s: &str,
+    ) {
+        let sk = SecretKey::<T>::from_scalar(scalar_from_hex(sk)).unwrap();
+        let (r_expected, s_expected) = (
+            scalar_from_hex::<T::Scalar>(r),
+            scalar_from_hex::<T::Scalar>(s),
+        );
+        let signature =
+            sign_with_nonce::<T, D>(&sk, message.as_bytes(), Nonce::Deterministic).unwrap();
+        assert_eq!(signature.r(), r_expected);
+        assert_eq!(signature.s(), normalize_s(s_expected));
+        assert!(
+            <T as ECDSAGroup<D>>::verify(signature, message.as_bytes(), sk.public_key()).is_ok()
+        );
+    }
+
+    /// RFC 6979 appendix A.2.5 (P-256 with SHA-256)
+    #[test]
+    fn rfc6979_p256_vectors() {
+        let sk = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+        let z = ecdsa_hash::<p256::ProjectivePoint, Sha256>(b"sample");
+        let k = rfc6979::generate_k::<p256::Scalar, Sha256>(&scalar_from_hex(sk), &z, &[]);
+        assert_eq!(
+            k,
+            scalar_from_hex("a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60")
+        );
+        check_deterministic_vector::<p256::ProjectivePoint, Sha256>(
+            sk,
+            "sample",
+            "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+            "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+        );
+        check_deterministic_vector::<p256::ProjectivePoint, Sha256>(
+            sk,
+            "test",
+            "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+            "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+        );
+    }
+
+    /// RFC 6979 appendix A.2.4 (P-224 with SHA-256). The digest is longer than the group order, so z is truncated to its leftmost 224 bits.
+    #[test]
+    fn rfc6979_p224_vectors() {
+        let sk = "f220266e1105bfe3083e03ec7a3a654651f45e37167e88600bf257c1";
+        let z = ecdsa_hash::<p224::ProjectivePoint, Sha256>(b"sample");
+        let k = rfc6979::generate_k::<p224::Scalar, Sha256>(&scalar_from_hex(sk), &z, &[]);
+        assert_eq!(
+            k,
+            scalar_from_hex("ad3029e0278f80643de33917ce6908c70a8ff50a411f06e41dedfcdc")
+        );
+        check_deterministic_vector::<p224::ProjectivePoint, Sha256>(
+            sk,
+            "sample",
+            "61aa3da010e8e8406c656bc477a7a7189895e7e840cdfe8ff42307ba",
+            "bc814050dab5d23770879494f9e0a680dc1af7161991bde692b10101",
+        );
+        check_deterministic_vector::<p224::ProjectivePoint, Sha256>(
+            sk,
+            "test",
+            "ad04dde87b84747a243a631ea47a1ba6d1faa059149ad2440de6fba6",
+            "178d49b1ae90e3d8b629be3db5683915f4e8c99fdf6e666cf37adcfd",
+        );
+    }
+
+    /// RFC 6979 appendix A.2.5 (P-256 with SHA-384 and SHA-512). Both digests are longer than the group order.
+    #[test]
+    fn rfc6979_p256_other_digests() {
+        let sk = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+        check_deterministic_vector::<p256::ProjectivePoint, Sha384>(
+            sk,
+            "sample",
+            "0eafea039b20e9b42309fb1d89e213057cbf973dc0cfc8f129edddc800ef7719",
+            "4861f0491e6998b9455193e34e7b0d284ddd7149a74b95b9261f13abde940954",
+        );
+        check_deterministic_vector::<p256::ProjectivePoint, Sha512>(
+            sk,
+            "sample",
+            "8496a60b5e9b47c825488827e0495b0e3fa109ec4568fd3f8d1097678eb97f00",
+            "2362ab1adbe2b8adf9cb9edab740ea6049c028114f2460f96554f61fae3302fe",
+        );
+    }
+
+    /// RFC 6979 appendix A.2.6 (P-384 with SHA-384)
+    #[test]
+    fn rfc6979_p384_vectors() {
+        let sk = "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba\
+                  9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5";
+        check_deterministic_vector::<p384::ProjectivePoint, Sha384>(
+            sk,
+            "sample",
+            "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c\
+             81a648152e44acf96e36dd1e80fabe46",
+            "99ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94f\
+             a329c145786e679e7b82c71a38628ac8",
+        );
+        check_deterministic_vector::<p384::ProjectivePoint, Sha384>(
+            sk,
+            "test",
+            "8203b63d3c853e8d77227fb377bcf7b7b772e97892a80f36ab775d509d7a5feb\
+             0542a7f0812998da8f1dd3ca3cf023db",
+            "ddd0760448d42d8a43af45af836fce4de8be06b485e9b61b827c2f13173923e0\
+             6a739f040649a667bf3b828246baa5a5",
+        );
+    }
+
+    #[test]
+    fn ecdsa_test_prehash() {
+        for _ in 1..100 {
+            ecdsa_test_prehash_aux()
+        }
+    }
+
+    /// Tests that signing a digest or a streamed hasher gives the same signature as signing the message, and that each form verifies
+    fn ecdsa_test_prehash_aux() {
+        let (sk, pk) = generate_random_signer();
+        let message = get_random_message(100);
+        let message_bytes = message.as_bytes();
+        let prehash = Sha256::digest(message_bytes);
+
+        // Feed the message to the hasher in pieces, like reading a large file
+        let mut digest = Sha256::new();
+        for chunk in message_bytes.chunks(7) {
+            digest.update(chunk);
+        }
+
+        let signature =
+            sign_with_nonce::<ProjectivePoint, Sha256>(&sk, message_bytes, Nonce::Deterministic)
+                .unwrap();
+        assert_eq!(
+            sign_prehash::<ProjectivePoint, Sha256>(&sk, &prehash, Nonce::Deterministic).unwrap(),
+            signature
+        );
+        assert_eq!(
+            sign_digest(&sk, digest.clone(), Nonce::Deterministic).unwrap(),
+            signature
+        );
+
+        assert!(<ProjectivePoint as ECDSAGroup>::verify_prehash(signature, &prehash, pk).is_ok());
+        assert!(ProjectivePoint::verify_digest(signature, digest.clone(), pk).is_ok());
+        digest.update(b"more");
+        assert!(ProjectivePoint::verify_digest(signature, digest, pk).is_err());
+
+        let (signature, v) = <ProjectivePoint as ECDSAGroup>::sign_prehash_with(
+            &sk,
+            &prehash,
+            Nonce::Random,
+            &mut rand::thread_rng(),
+        )
+        .unwrap();
+        assert_eq!(
+            recover_public_key_prehash((signature, v), &prehash),
+            Some(pk)
+        );
+    }
+
+    /// Ethereum-style signing: Keccak-256 message hash with public key recovery
+    #[test]
+    fn ecdsa_test_keccak256() {
+        let (sk, pk) = generate_random_signer();
+        let message = get_random_message(10);
+        let message_bytes = message.as_bytes();
+
+        let (signature, v) = sign_recoverable::<ProjectivePoint, Keccak256>(
+            &sk,
+            message_bytes,
+            Nonce::Deterministic,
+        )
+        .unwrap();
+        assert!(
+            <ProjectivePoint as ECDSAGroup<Keccak256>>::verify(signature, message_bytes, pk)
+                .is_ok()
+        );
+        assert!(<ProjectivePoint as ECDSAGroup>::verify(signature, message_bytes, pk).is_err());
+        assert_eq!(
+            recover_public_key::<_, Keccak256>((signature, v), message_bytes),
+            Some(pk)
+        );
+    }
+
+    /// The RFC 6979 appendix has no secp256k1 vectors; these are the widely used ones from the Trezor and bitcoinjs test suites
+    #[test]
+    fn rfc6979_secp256k1_vectors() {
+        let one = "0000000000000000000000000000000000000000000000000000000000000001";
+        let n_minus_one = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140";
+        check_deterministic_vector::<ProjectivePoint, Sha256>(
+            one,
+            "Satoshi Nakamoto",
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8",
+            "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+        );
+        check_deterministic_vector::<ProjectivePoint, Sha256>(
+            one,
+            "All those moments will be lost in time, like tears in rain. Time to die...",
+            "8600dbd41e348fe5c9465ab92d23e3db8b98b873beecd930736488696438cb6b",
+            "547fe64427496db33bf66019dacbf0039c04199abb0122918601db38a72cfc21",
+        );
+        check_deterministic_vector::<ProjectivePoint, Sha256>(
+            n_minus_one,
+            "Satoshi Nakamoto",
+            "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d0",
+            "6b39cd0eb1bc8603e159ef5c20a5c8ad685a45b06ce9bebed3f153d10d93bed5",
+        );
+    }
+
+    /// Hedged signatures must verify and differ between calls, unlike deterministic ones
+    #[test]
+    fn ecdsa_test_hedged() {
+        let (sk, pk) = generate_random_signer();
+        let message = get_random_message(10);
+        let message_bytes = message.as_bytes();
+
+        let deterministic =
+            sign_with_nonce::<ProjectivePoint, Sha256>(&sk, message_bytes, Nonce::Deterministic)
+                .unwrap();
+        assert_eq!(
+            deterministic,
+            sign_with_nonce::<ProjectivePoint, Sha256>(&sk, message_bytes, Nonce::Deterministic)
+                .unwrap()
+        );
+
+        let hedged =
+            sign_with_nonce::<ProjectivePoint, Sha256>(&sk, message_bytes, Nonce::Hedged).unwrap();
+        assert_ne!(
+            hedged,
+            sign_with_nonce::<ProjectivePoint, Sha256>(&sk, message_bytes, Nonce::Hedged).unwrap()
+        );
+        assert!(<ProjectivePoint as ECDSAGroup>::verify(hedged, message_bytes, pk).is_ok());
+    }
+
+    /// Recovery ids for the secp256k1 vectors above, matching the k256 crate's recoverable signatures
+    #[test]
+    fn recovery_secp256k1_vectors() {
+        let one = "0000000000000000000000000000000000000000000000000000000000000001";
+        let n_minus_one = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140";
+        let vectors = [
+            (one, "Satoshi Nakamoto", 1),
+            (
+                one,
+                "All those moments will be lost in time, like tears in rain. Time to die...",
+                0,
+            ),
+            (n_minus_one, "Satoshi Nakamoto", 0),
+        ];
+        for (sk, message, v_expected) in vectors {
+            let sk = SecretKey::from_scalar(scalar_from_hex::<Scalar>(sk)).unwrap();
+            let (signature, v) = sign_recoverable::<ProjectivePoint, Sha256>(
+                &sk,
+                message.as_bytes(),
+                Nonce::Deterministic,
+            )
+            .unwrap();
+            assert_eq!(v, v_expected);
+            let recovered =
+                recover_public_key::<ProjectivePoint, Sha256>((signature, v), message.as_bytes());
+            assert_eq!(recovered, Some(sk.public_key()));
+        }
+    }
+
+    #[test]
+    fn ecdsa_test_recovery() {
+        for _ in 1..100 {
+            ecdsa_test_recovery_aux()
+        }
+    }
+
+    /// Tests that the public key recovered from a random signature is the signer's, and that a different message or recovery id gives a different key
+    fn ecdsa_test_recovery_aux() {
+        let (sk, pk) = generate_random_signer();
+        let message = get_random_message(10);
+        let message_bytes = message.as_bytes();
+
+        let (signature, v) =
+            sign_recoverable::<ProjectivePoint, Sha256>(&sk, message_bytes, Nonce::Random).unwrap();
+        assert_eq!(
+            recover_public_key::<_, Sha256>((signature, v), message_bytes),
+            Some(pk)
+        );
+
+        let message_altered = get_random_message(11);
+        assert_ne!(
+            recover_public_key::<_, Sha256>((signature, v), message_altered.as_bytes()),
+            Some(pk)
+        );
+        assert_ne!(
+            recover_public_key::<_, Sha256>((signature, v ^ 1), message_bytes),
+            Some(pk)
+        );
+        assert_eq!(
+            recover_public_key::<ProjectivePoint, Sha256>((signature, 4), message_bytes),
+            None
+        );
+    }
+
+    #[test]
+    fn signature_encoding_vector() {
+        let sk = SecretKey::<ProjectivePoint>::from_scalar(Scalar::ONE).unwrap();
+        let signature = sign_with_nonce::<ProjectivePoint, Sha256>(
+            &sk,
+            b"Satoshi Nakamoto",
+            Nonce::Deterministic,
+        )
+        .unwrap();
+        let compact = "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+                       2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5";
+        // r has its top bit set, so it gets a zero byte in front
+        let der = "3045\
+                   0221\
+                   00934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+                   0220\
+                   2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5";
+        assert_eq!(signature.to_bytes(), bytes_from_hex(compact));
+        assert_eq!(signature.to_der(), bytes_from_hex(der));
+        assert_eq!(
+            Signature::<Scalar>::from_bytes(&bytes_from_hex(compact)),
+            Some(signature)
+        );
+        assert_eq!(
+            Signature::<Scalar>::from_der(&bytes_from_hex(der)),
+            Some(signature)
+        );
+    }
+
+    #[test]
+    fn signature_encoding_round_trip() {
+        for _ in 1..100 {
+            let (sk, _) = generate_random_signer();
+            let message = get_random_message(10);
+            let signature = <ProjectivePoint as ECDSAGroup>::sign(&sk, message.as_bytes()).unwrap();
+
+            let compact = signature.to_bytes();
+            assert_eq!(compact.len(), 64);
+            assert_eq!(Signature::from_bytes(&compact), Some(signature));
+            assert_eq!(Signature::from_der(&signature.to_der()), Some(signature));
+        }
+
+        // Short integers are encoded minimally
+        let small = Signature::new(Scalar::ONE, Scalar::from(0x80u64));
+        let der = bytes_from_hex("300702010102020080");
+        assert_eq!(small.to_der(), der);
+        assert_eq!(Signature::from_der(&der), Some(small));
+    }
+
+    #[test]
+    fn signature_der_rejects_non_canonical() {
+        let reject = |hex: &str| Signature::<Scalar>::from_der(&bytes_from_hex(hex)).is_none();
+        // Valid encoding of (1, 1) to compare against
+        assert!(!reject("3006020101020101"));
+        // Wrong sequence or integer tag
+        assert!(reject("3106020101020101"));
+        assert!(reject("3006030101020101"));
+        // Sequence length does not match the contents, or trailing bytes
+        assert!(reject("3007020101020101"));
+        assert!(reject("300602010102010100"));
+        // Non-minimal long form length
+        assert!(reject("308106020101020101"));
+        // Unnecessary zero padding
+        assert!(reject("300702020001020101"));
+        // Negative integer
+        assert!(reject("3006020181020101"));
+        // Empty integer
+        assert!(reject("30050200020101"));
+        // Zero
+        assert!(reject("3006020100020101"));
+        // r = n is out of range
+        assert!(reject(
+            "3026\
+             0221\
+             00fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141\
+             020101"
+        ));
+        // Compact form with the wrong length or s = 0
+        assert!(Signature::<Scalar>::from_bytes(&[1u8; 63]).is_none());
+        let mut zero_s = [0u8; 64];
+        zero_s[31] = 1;
+        assert!(Signature::<Scalar>::from_bytes(&zero_s).is_none());
+    }
+
+    #[test]
+    fn ecdsa_test_low_s() {
+        for _ in 1..100 {
+            ecdsa_test_low_s_aux()
+        }
+    }
+
+    /// Tests that signatures are always low-S and that strict verification rejects the high-S twin of a valid signature
+    fn ecdsa_test_low_s_aux() {
+        let (sk, pk) = generate_random_signer();
+        let message = get_random_message(10);
+        let message_bytes = message.as_bytes();
+
+        let signature = <ProjectivePoint as ECDSAGroup>::sign(&sk, message_bytes).unwrap();
+        let (r, s) = (signature.r(), signature.s());
+        assert!(!is_high_s(&s));
+        assert_eq!(normalize_s(-s), s);
+        assert!(
+            <ProjectivePoint as ECDSAGroup>::verify_strict(signature, message_bytes, pk).is_ok()
+        );
+
+        // (r, n - s) is still a valid signature, but is malleated
+        let malleated = Signature::new(r, -s);
+        assert!(<ProjectivePoint as ECDSAGroup>::verify(malleated, message_bytes, pk).is_ok());
+        assert_eq!(
+            <ProjectivePoint as ECDSAGroup>::verify_strict(malleated, message_bytes, pk),
+            Err(Error::HighS)
+        );
+
+        let prehash = Sha256::digest(message_bytes);
+        assert!(
+            <ProjectivePoint as ECDSAGroup>::verify_prehash_strict(signature, &prehash, pk).is_ok()
+        );
+        assert!(<ProjectivePoint as ECDSAGroup>::verify_prehash(malleated, &prehash, pk).is_ok());
+        assert_eq!(
+            <ProjectivePoint as ECDSAGroup>::verify_prehash_strict(malleated, &prehash, pk),
+            Err(Error::HighS)
+        );
+    }
+
+    /// Random and hedged nonces come from the given RNG, so a seeded RNG reproduces the signature
+    #[test]
+    fn ecdsa_test_seeded_rng() {
+        let key_pair = KeyPair::<ProjectivePoint>::random_with_rng(&mut StdRng::seed_from_u64(9));
+        let sk = key_pair.secret_key();
+        let message = b"seeded";
+        for nonce in [Nonce::Random, Nonce::Hedged] {
+            let sign = |seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                <ProjectivePoint as ECDSAGroup>::sign_prehash_with(
+                    sk,
+                    &Sha256::digest(message),
+                    nonce,
+                    &mut rng,
+                )
+                .unwrap()
+                .0
+            };
+            assert_eq!(sign(10), sign(10));
+            assert_ne!(sign(10), sign(11));
+            assert!(<ProjectivePoint as ECDSAGroup>::verify(
+                sign(10),
+                message,
+                key_pair.public_key()
+            )
+            .is_ok());
+        }
+    }
 
-        let verifier = ProjectivePoint::verify(signature, message_bytes.as_ref(), pk);
+    /// Malformed signatures that used to panic must be rejected with an error
+    #[test]
+    fn ecdsa_test_degenerate_inputs() {
+        let (sk, pk) = generate_random_signer();
+        let message = b"degenerate";
+        let signature = <ProjectivePoint as ECDSAGroup>::sign(&sk, message).unwrap();
+        let verify =
+            |signature, pk| <ProjectivePoint as ECDSAGroup>::verify(signature, message, pk);
 
-        assert_eq!(verifier, true);
+        assert_eq!(
+            verify(Signature::new(signature.r(), Scalar::ZERO), pk),
+            Err(Error::ZeroScalar)
+        );
+        assert_eq!(
+            verify(Signature::new(Scalar::ZERO, signature.s()), pk),
+            Err(Error::ZeroScalar)
+        );
+        // u1 * G + u2 * pk is the identity when pk = -(z / r) * G
+        let z = ecdsa_hash::<ProjectivePoint, Sha256>(message);
+        let r = signature.r();
+        let pk_cancel =
+            PublicKey::from_point(ProjectivePoint::GENERATOR * -(z * r.invert().unwrap())).unwrap();
+        assert_eq!(
+            verify(Signature::new(r, Scalar::ONE), pk_cancel),
+            Err(Error::IdentityPoint)
+        );
     }
 
     /// Tests whether a random message replaced by a random message with a different length (cannot be the same as the original) correctly fails to verify.
@@ -190,15 +1055,16 @@ mod test {
 
         let message = get_random_message(10);
         let message_bytes = message.as_bytes();
-        
+
         //Create 11 character message that must be different from the original message
         let message_altered = get_random_message(11);
         let message_altered_bytes = message_altered.as_bytes();
 
-        let signature = ProjectivePoint::sign(sk, message_bytes.as_ref());
+        let signature = <ProjectivePoint as ECDSAGroup>::sign(&sk, message_bytes).unwrap();
 
-        let verifier = ProjectivePoint::verify(signature, message_altered_bytes.as_ref(), pk);
+        let verifier =
+            <ProjectivePoint as ECDSAGroup>::verify(signature, message_altered_bytes, pk);
 
-        assert_eq!(verifier, false);
+        assert_eq!(verifier, Err(Error::InvalidSignature));
     }
 }
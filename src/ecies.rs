@@ -0,0 +1,206 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    Aes256Gcm,
+};
+use elliptic_curve::group::GroupEncoding;
+use hkdf::Hkdf;
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{ecdh::ECDHGroup, point_from_bytes, Error, PublicKey, SecretKey};
+
+// ECIES: hybrid public key encryption. The sender makes an ephemeral key pair, runs ECDH between the ephemeral secret key and the
+// recipient's public key, and derives an AES-256-GCM key from the shared point with HKDF-SHA256. The recipient repeats the ECDH with
+// its secret key and the ephemeral public key, which travels with the ciphertext:
+// ephemeral public key || nonce || ciphertext || tag
+// with the ephemeral public key in its `GroupEncoding`, a 12-byte nonce and a 16-byte tag.
+
+/// Length of the AES-GCM nonce
+const NONCE_LEN: usize = 12;
+
+/// Length of the AES-GCM authentication tag
+const TAG_LEN: usize = 16;
+
+/// HKDF info string that separates ECIES keys from other keys derived from the same shared point
+const KDF_INFO: &[u8] = b"elliptic_curves ECIES AES-256-GCM";
+
+/// Encrypt a plaintext to a public key. The associated data `aad` is authenticated but not encrypted, and must be passed to `decrypt` unchanged.
+/// Fails with `Error::EncryptionFailed` if the plaintext or associated data is longer than AES-GCM allows.
+pub fn encrypt<G: ECDHGroup + GroupEncoding>(
+    pk: PublicKey<G>,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+    encrypt_with_rng(pk, plaintext, aad, &mut rand::thread_rng())
+}
+
+/// Like `encrypt`, drawing the ephemeral key and the nonce from the given RNG
+pub fn encrypt_with_rng<G: ECDHGroup + GroupEncoding>(
+    pk: PublicKey<G>,
+    plaintext: &[u8],
+    aad: &[u8],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Vec<u8>, Error> {
+    let ephemeral_sk = SecretKey::<G>::random_with_rng(&mut *rng);
+    let ephemeral_pk = ephemeral_sk.public_key().point().to_bytes();
+    let cipher = cipher(G::generate_secret(&ephemeral_sk, pk), ephemeral_pk.as_ref());
+    let nonce = Aes256Gcm::generate_nonce(&mut *rng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::EncryptionFailed)?;
+
+    let mut bytes = ephemeral_pk.as_ref().to_vec();
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+/// Decrypt a ciphertext from `encrypt` with the recipient's secret key and the same associated data.
+/// Fails with `Error::InvalidEncoding` if the ciphertext is too short or the ephemeral public key does not decode, with
+/// `Error::IdentityPoint` if it is the identity, and with `Error::DecryptionFailed` if the ciphertext, associated data or key is wrong.
+pub fn decrypt<G: ECDHGroup + GroupEncoding>(
+    sk: &SecretKey<G>,
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let pk_len = G::Repr::default().as_ref().len();
+    if ciphertext.len() < pk_len + NONCE_LEN + TAG_LEN {
+        return Err(Error::InvalidEncoding);
+    }
+    let (ephemeral_pk, rest) = ciphertext.split_at(pk_len);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let point = PublicKey::from_point(point_from_bytes::<G>(ephemeral_pk)?)?;
+
+    let cipher = cipher(G::generate_secret(sk, point), ephemeral_pk);
+    cipher
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::DecryptionFailed)
+}
+
+/// The AES-256-GCM cipher keyed with HKDF-SHA256 of the shared point, salted with the ephemeral public key so that the key
+/// is bound to it
+fn cipher<G: GroupEncoding>(shared_point: G, ephemeral_pk: &[u8]) -> Aes256Gcm {
+    let ikm = Zeroizing::new(shared_point.to_bytes().as_ref().to_vec());
+    let hkdf = Hkdf::<Sha256>::new(Some(ephemeral_pk), &ikm);
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf.expand(KDF_INFO, key.as_mut())
+        .expect("32 bytes is a valid length for HKDF-SHA256");
+    Aes256Gcm::new(key.as_ref().into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KeyPair;
+    use k256::ProjectivePoint;
+    use rand::Rng;
+
+    #[test]
+    fn ecies_test_round_trip() {
+        for _ in 1..100 {
+            let key_pair = KeyPair::<ProjectivePoint>::random();
+            let length = rand::thread_rng().gen_range(0..100);
+            let plaintext: Vec<u8> = (0..length).map(|_| rand::random()).collect();
+            let ciphertext = encrypt(key_pair.public_key(), &plaintext, b"aad").unwrap();
+            assert_eq!(ciphertext.len(), 33 + NONCE_LEN + plaintext.len() + TAG_LEN);
+            assert_eq!(
+                decrypt(key_pair.secret_key(), &ciphertext, b"aad"),
+                Ok(plaintext)
+            );
+        }
+    }
+
+    #[test]
+    fn ecies_test_p256() {
+        let key_pair = KeyPair::<p256::ProjectivePoint>::random();
+        let ciphertext = encrypt(key_pair.public_key(), b"p256", &[]).unwrap();
+        assert_eq!(
+            decrypt(key_pair.secret_key(), &ciphertext, &[]),
+            Ok(b"p256".to_vec())
+        );
+    }
+
+    /// Test that the AES key is HKDF-SHA256 of the encoded shared point, salted with the ephemeral public key
+    #[test]
+    fn ecies_test_key_derivation() {
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let ciphertext = encrypt(key_pair.public_key(), b"plaintext", b"aad").unwrap();
+        let (ephemeral_pk, rest) = ciphertext.split_at(33);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let shared_point = ProjectivePoint::generate_secret(
+            key_pair.secret_key(),
+            PublicKey::from_point(point_from_bytes(ephemeral_pk).unwrap()).unwrap(),
+        );
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(ephemeral_pk), &shared_point.to_bytes())
+            .expand(KDF_INFO, &mut key)
+            .unwrap();
+        let plaintext = Aes256Gcm::new(&key.into())
+            .decrypt(
+                nonce.into(),
+                Payload {
+                    msg: ciphertext,
+                    aad: b"aad",
+                },
+            )
+            .unwrap();
+        assert_eq!(plaintext, b"plaintext");
+    }
+
+    /// Test that decryption fails for a wrong key, wrong associated data, and any changed byte
+    #[test]
+    fn ecies_test_rejects_tampering() {
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let ciphertext = encrypt(key_pair.public_key(), b"secret message", b"header").unwrap();
+
+        let other = KeyPair::<ProjectivePoint>::random();
+        assert_eq!(
+            decrypt(other.secret_key(), &ciphertext, b"header"),
+            Err(Error::DecryptionFailed)
+        );
+        assert_eq!(
+            decrypt(key_pair.secret_key(), &ciphertext, b"other"),
+            Err(Error::DecryptionFailed)
+        );
+        for i in 33..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                decrypt(key_pair.secret_key(), &tampered, b"header"),
+                Err(Error::DecryptionFailed)
+            );
+        }
+        // Flipping a bit of the ephemeral key may leave bytes that are not a point
+        let mut tampered = ciphertext.clone();
+        tampered[1] ^= 1;
+        assert!(decrypt(key_pair.secret_key(), &tampered, b"header").is_err());
+
+        assert_eq!(
+            decrypt(
+                key_pair.secret_key(),
+                &ciphertext[..33 + NONCE_LEN + TAG_LEN - 1],
+                b"header"
+            ),
+            Err(Error::InvalidEncoding)
+        );
+        let mut identity = ciphertext.clone();
+        identity[..33].copy_from_slice(&ProjectivePoint::IDENTITY.to_bytes());
+        assert_eq!(
+            decrypt(key_pair.secret_key(), &identity, b"header"),
+            Err(Error::IdentityPoint)
+        );
+    }
+}
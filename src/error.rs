@@ -0,0 +1,81 @@
+use std::fmt;
+
+/// Errors returned when signing, verifying, encrypting, decrypting, deriving keys, decoding or running the MuSig and FROST rounds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A scalar that must be nonzero was zero, such as r or s of a signature or a secret key
+    ZeroScalar,
+    /// A point that must not be the identity was the identity, such as a public key
+    IdentityPoint,
+    /// An ECDSA signature has s > n/2 and strict verification was requested
+    HighS,
+    /// Bytes could not be decoded as a key or signature
+    InvalidEncoding,
+    /// The signature does not verify for the message and public key
+    InvalidSignature,
+    /// A plaintext could not be encrypted because it or the associated data is longer than the cipher allows
+    EncryptionFailed,
+    /// A ciphertext could not be decrypted: the key or associated data is wrong, or the ciphertext was modified
+    DecryptionFailed,
+    /// A key derivation function was asked for more output than it can produce
+    InvalidKeyLength,
+    /// A public key or signer index is not among the signers of a MuSig or FROST session
+    UnknownSigner,
+    /// A MuSig round message was received twice from the same signer
+    DuplicateMessage,
+    /// A MuSig round cannot finish until every signer's message has been received
+    MissingMessages,
+    /// A MuSig signer's secret nonce was already used to sign, and signing again with it would leak the secret key
+    NonceReused,
+    /// A MuSig round message carries the ID of a different session, or a signer was asked to sign another message than the one its session is bound to
+    SessionMismatch,
+    /// Partial signatures in a MuSig or FROST session did not verify; holds the indices (identifiers in FROST) of the signers that sent them
+    InvalidPartialSignatures(Vec<usize>),
+    /// A threshold must be at least 1 and at most the number of participants
+    InvalidThreshold,
+    /// Key generation messages or secret shares did not verify against their commitments; holds the identifiers of the participants that sent them
+    InvalidShares(Vec<usize>),
+    /// A batch of signatures did not verify; holds the indices of the signatures that fail on their own
+    InvalidBatch(Vec<usize>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Error::ZeroScalar => "scalar must not be zero",
+            Error::IdentityPoint => "point must not be the identity",
+            Error::HighS => "signature is not in low-S form",
+            Error::InvalidEncoding => "invalid encoding",
+            Error::InvalidSignature => "signature does not verify",
+            Error::EncryptionFailed => "plaintext could not be encrypted",
+            Error::DecryptionFailed => "ciphertext could not be decrypted",
+            Error::InvalidKeyLength => "key length is too long for the key derivation function",
+            Error::UnknownSigner => "signer is not part of the session",
+            Error::DuplicateMessage => "message already received from this signer",
+            Error::MissingMessages => "messages from some signers are missing",
+            Error::NonceReused => "nonce has already been used to sign",
+            Error::SessionMismatch => "message belongs to a different session",
+            Error::InvalidPartialSignatures(indices) => {
+                return write!(
+                    f,
+                    "partial signatures from signers {:?} do not verify",
+                    indices
+                )
+            }
+            Error::InvalidThreshold => "threshold must be between 1 and the number of participants",
+            Error::InvalidShares(identifiers) => {
+                return write!(
+                    f,
+                    "shares from participants {:?} do not match their commitments",
+                    identifiers
+                )
+            }
+            Error::InvalidBatch(indices) => {
+                return write!(f, "signatures at indices {:?} do not verify", indices)
+            }
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for Error {}
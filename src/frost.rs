@@ -0,0 +1,1223 @@
+use elliptic_curve::{group::GroupEncoding, Field, Group};
+use rand::{CryptoRng, RngCore};
+use sha2::{
+    digest::{core_api::BlockSizeUser, Output},
+    Digest, Sha256,
+};
+use std::{fmt, marker::PhantomData};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+use crate::{
+    all_received, hash_to_field,
+    keys::SecretScalar,
+    msm::msm,
+    receive, scalar_to_bytes,
+    schnorr::{verify_equations, Equation},
+    sharing::{
+        feldman_public_share, feldman_split_with_rng, feldman_verify, lagrange_coefficient, Share,
+    },
+    Error, PublicKey, SecretKey,
+};
+
+// FROST: Flexible Round-Optimized Schnorr Threshold signatures, following RFC 9591
+// https://www.rfc-editor.org/rfc/rfc9591.html
+// A group key is shared among n participants so that any t of them can sign, while fewer than t learn nothing about it.
+// Key shares come from a trusted dealer with `trusted_dealer_keygen`, or from a distributed key generation between the participants
+// with `DkgParticipant`, after which no one knows the group secret key.
+//
+// Signing takes two rounds. Each signer publishes a `SigningCommitment` to a pair of single-use nonces from `KeyShare::commit`,
+// the coordinator puts t or more commitments and the message in a `SigningPackage`, each of those signers answers with a
+// `SignatureShare` from `sign`, and `aggregate` checks the shares and sums them into a signature (z, R), which `verify` checks
+// against the group public key.
+//
+// The nonces, binding factors and challenge use the hash functions H1 to H5 of RFC 9591, with the context string of the ciphersuite
+// given by `FrostGroup`: FROST(secp256k1, SHA-256) and FROST(P-256, SHA-256). The challenge is H2(R || PK || message) rather than the
+// one of the `schnorr` module, so signatures verify with `verify` and not with `SchnorrGroup::verify`.
+// The RFC leaves key generation out; the proofs of knowledge of the DKG hash with HDKG, under the same context string, and a context
+// that the participants agree on for each run.
+//
+// Participants are identified by 1..=n, the points at which the sharing polynomial of the `sharing` module is evaluated.
+
+/// A group and hash function that make up a FROST ciphersuite of RFC 9591, named by the context string that begins the domain
+/// separation tags of its hash functions
+pub trait FrostGroup<D: Digest + BlockSizeUser = Sha256>: Group + GroupEncoding {
+    /// The contextString of the ciphersuite
+    const CONTEXT_STRING: &'static str;
+}
+
+impl FrostGroup for k256::ProjectivePoint {
+    const CONTEXT_STRING: &'static str = "FROST-secp256k1-SHA256-v1";
+}
+
+/// FROST(P-256, SHA-256), behind the cargo feature p256. The unit tests always build it, as p256 is also a dev-dependency.
+#[cfg(any(feature = "p256", test))]
+impl FrostGroup for p256::ProjectivePoint {
+    const CONTEXT_STRING: &'static str = "FROST-P256-SHA256-v1";
+}
+
+/// A participant's share of the group key: its identifier, its secret share s_i = f(i) of the group secret key f(0), and the public key package
+#[derive(Debug, Clone)]
+pub struct KeyShare<G: Group> {
+    identifier: usize,
+    signing_share: SecretKey<G>,
+    public_key_package: PublicKeyPackage<G>,
+}
+
+impl<G: Group + GroupEncoding> KeyShare<G> {
+    /// Returns the participant's identifier
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Returns the participant's secret share s_i
+    pub fn signing_share(&self) -> &SecretKey<G> {
+        &self.signing_share
+    }
+
+    /// Returns the public key package shared by all participants
+    pub fn public_key_package(&self) -> &PublicKeyPackage<G> {
+        &self.public_key_package
+    }
+
+    /// Returns the group public key that signatures verify against
+    pub fn group_public_key(&self) -> PublicKey<G> {
+        self.public_key_package.group_public_key
+    }
+
+    /// Check a share from a trusted dealer against the dealer's commitments C_k = a_k * G to the sharing polynomial:
+    /// s_i * G == sum(C_k * i^k), and C_0 is the group public key. Fails with `Error::InvalidShares` naming this participant otherwise.
+    pub fn verify(&self, commitments: &[G]) -> Result<(), Error> {
+        if commitments.first() != Some(&self.group_public_key().point()) {
+            return Err(Error::InvalidShares(vec![self.identifier]));
+        }
+        if G::generator() * self.signing_share.scalar()
+            != feldman_public_share(commitments, self.identifier)
+        {
+            return Err(Error::InvalidShares(vec![self.identifier]));
+        }
+        Ok(())
+    }
+
+    /// Round 1 of signing: generate fresh nonces and the commitment to them that is sent to the coordinator.
+    /// D is the hash function of the ciphersuite, the same as for the `SigningPackage`.
+    pub fn commit<D: Digest + BlockSizeUser>(&self) -> (SigningNonces<G>, SigningCommitment<G>)
+    where
+        G: FrostGroup<D>,
+    {
+        self.commit_with_rng::<D>(&mut rand::thread_rng())
+    }
+
+    /// Like `commit`, drawing the nonces from the given RNG
+    pub fn commit_with_rng<D: Digest + BlockSizeUser>(
+        &self,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (SigningNonces<G>, SigningCommitment<G>)
+    where
+        G: FrostGroup<D>,
+    {
+        let mut hiding_randomness = Zeroizing::new([0u8; 32]);
+        let mut binding_randomness = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(hiding_randomness.as_mut());
+        rng.fill_bytes(binding_randomness.as_mut());
+        self.commit_with_randomness::<D>(hiding_randomness.as_ref(), binding_randomness.as_ref())
+    }
+
+    /// The nonce_generate of RFC 9591, H3(random_bytes || SerializeScalar(s_i)): the nonces hash fresh randomness with the secret share,
+    /// so that a weak RNG alone does not expose them
+    fn commit_with_randomness<D: Digest + BlockSizeUser>(
+        &self,
+        hiding_randomness: &[u8],
+        binding_randomness: &[u8],
+    ) -> (SigningNonces<G>, SigningCommitment<G>)
+    where
+        G: FrostGroup<D>,
+    {
+        let sk = Zeroizing::new(scalar_to_bytes(self.signing_share.scalar()));
+        let hiding = h3::<G, D>(&[hiding_randomness, &sk]);
+        let binding = h3::<G, D>(&[binding_randomness, &sk]);
+        let commitment = SigningCommitment {
+            identifier: self.identifier,
+            hiding: G::generator() * hiding,
+            binding: G::generator() * binding,
+        };
+        let nonces = SigningNonces {
+            hiding: SecretScalar(hiding),
+            binding: SecretScalar(binding),
+            commitment,
+        };
+        (nonces, commitment)
+    }
+}
+
+/// The public outcome of key generation: the threshold, the group public key, and every participant's verifying share Y_i = s_i * G
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKeyPackage<G: Group> {
+    threshold: usize,
+    group_public_key: PublicKey<G>,
+    verifying_shares: Vec<G>,
+}
+
+impl<G: Group> PublicKeyPackage<G> {
+    /// Derive the package from the Feldman commitments C_k to the coefficients of the sharing polynomial f: f(0) * G = C_0 and f(i) * G = sum(C_k * i^k)
+    fn from_commitments(commitments: &[G], participants: usize) -> Result<Self, Error> {
+        Ok(PublicKeyPackage {
+            threshold: commitments.len(),
+            group_public_key: PublicKey::from_point(commitments[0])?,
+            verifying_shares: (1..=participants)
+                .map(|identifier| feldman_public_share(commitments, identifier))
+                .collect(),
+        })
+    }
+
+    /// Returns the number of participants needed to sign
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the number of participants
+    pub fn participants(&self) -> usize {
+        self.verifying_shares.len()
+    }
+
+    /// Returns the group public key
+    pub fn group_public_key(&self) -> PublicKey<G> {
+        self.group_public_key
+    }
+
+    /// Returns the verifying share Y_i of the participant with the given identifier, if there is one
+    pub fn verifying_share(&self, identifier: usize) -> Option<G> {
+        let index = identifier.checked_sub(1)?;
+        self.verifying_shares.get(index).copied()
+    }
+}
+
+/// Split a secret key into key shares for `participants` participants, any `threshold` of whom can sign, and return them together with
+/// the commitments to the sharing polynomial that each participant checks its share against with `KeyShare::verify`.
+/// The dealer learns every share, so it must be trusted. Fails with `Error::InvalidThreshold` unless 1 <= threshold <= participants.
+pub fn trusted_dealer_keygen<G: Group + GroupEncoding>(
+    secret_key: &SecretKey<G>,
+    threshold: usize,
+    participants: usize,
+) -> Result<(Vec<KeyShare<G>>, Vec<G>), Error> {
+    trusted_dealer_keygen_with_rng(secret_key, threshold, participants, &mut rand::thread_rng())
+}
+
+/// Like `trusted_dealer_keygen`, drawing the coefficients of the sharing polynomial from the given RNG
+pub fn trusted_dealer_keygen_with_rng<G: Group + GroupEncoding>(
+    secret_key: &SecretKey<G>,
+    threshold: usize,
+    participants: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(Vec<KeyShare<G>>, Vec<G>), Error> {
+    let (shares, commitments) =
+        feldman_split_with_rng(*secret_key.scalar(), threshold, participants, rng)?;
+    let public_key_package = PublicKeyPackage::from_commitments(&commitments, participants)?;
+    let key_shares = shares
+        .into_iter()
+        .map(|share| {
+            Ok(KeyShare {
+                identifier: share.identifier(),
+                signing_share: SecretKey::from_scalar(share.value())?,
+                public_key_package: public_key_package.clone(),
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+    Ok((key_shares, commitments))
+}
+
+/// One participant's side of the distributed key generation from the FROST paper: Pedersen's DKG, where every participant deals a
+/// random secret with Feldman commitments and proves knowledge of it, and the group secret key is the sum of all of them.
+/// Round 1 broadcasts a `DkgCommitmentMessage`; round 2 sends each other participant its `DkgShareMessage` over a confidential channel.
+/// D is the hash function of the ciphersuite, SHA-256 unless another is given.
+#[derive(Debug)]
+pub struct DkgParticipant<G: Group, D: Digest = Sha256> {
+    identifier: usize,
+    threshold: usize,
+    context: Vec<u8>,
+    outgoing_shares: Vec<Share<G>>,
+    commitment_messages: Vec<Option<DkgCommitmentMessage<G>>>,
+    shares: Vec<Option<G::Scalar>>,
+    digest: PhantomData<D>,
+}
+
+impl<G: FrostGroup<D>, D: Digest + BlockSizeUser> DkgParticipant<G, D> {
+    /// Start the key generation as participant `identifier` of `participants`, any `threshold` of whom will be able to sign.
+    /// The context must be the same for all participants and unique to this run, such as a session ID and the participants' public keys,
+    /// so that proofs of knowledge cannot be replayed from another run. Returns the participant and its commitment message, which is
+    /// broadcast to all other participants.
+    pub fn new(
+        identifier: usize,
+        threshold: usize,
+        participants: usize,
+        context: &[u8],
+    ) -> Result<(Self, DkgCommitmentMessage<G>), Error> {
+        Self::new_with_rng(
+            identifier,
+            threshold,
+            participants,
+            context,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Like `new`, drawing the secret, its sharing polynomial and the proof nonce from the given RNG
+    pub fn new_with_rng(
+        identifier: usize,
+        threshold: usize,
+        participants: usize,
+        context: &[u8],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self, DkgCommitmentMessage<G>), Error> {
+        let secret = G::Scalar::random(&mut *rng);
+        let (outgoing_shares, commitments) =
+            feldman_split_with_rng(secret, threshold, participants, rng)?;
+        let index = identifier_index(identifier, participants)?;
+
+        // Schnorr proof of knowledge of the secret a_0, so that no participant can pick its commitment to cancel out the others'
+        let k = G::Scalar::random(&mut *rng);
+        let r_point = G::generator() * k;
+        let c = proof_challenge::<G, D>(context, identifier, commitments[0], r_point);
+        let message = DkgCommitmentMessage {
+            identifier,
+            commitments,
+            proof: (r_point, k + secret * c),
+        };
+
+        let mut participant = DkgParticipant {
+            identifier,
+            threshold,
+            context: context.to_vec(),
+            outgoing_shares,
+            commitment_messages: vec![None; participants],
+            shares: vec![None; participants],
+            digest: PhantomData,
+        };
+        participant.commitment_messages[index] = Some(message.clone());
+        participant.shares[index] = Some(participant.share_for(identifier)?.share);
+        Ok((participant, message))
+    }
+
+    /// Returns the participant's identifier
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Take in another participant's commitment message, failing with `Error::InvalidShares` naming the sender unless it has one
+    /// commitment per coefficient and its proof of knowledge holds for this run's context
+    pub fn receive_commitment(&mut self, message: &DkgCommitmentMessage<G>) -> Result<(), Error> {
+        let index = identifier_index(message.identifier, self.shares.len())?;
+        if !message.verify::<D>(self.threshold, &self.context) {
+            return Err(Error::InvalidShares(vec![message.identifier]));
+        }
+        receive(&mut self.commitment_messages, index, message.clone())
+    }
+
+    /// Round 2: The secret share f(l) of this participant's polynomial for participant l, to be sent over a confidential and authenticated channel
+    pub fn share_for(&self, recipient: usize) -> Result<DkgShareMessage<G>, Error> {
+        let index = identifier_index(recipient, self.shares.len())?;
+        Ok(DkgShareMessage {
+            sender: self.identifier,
+            recipient,
+            share: self.outgoing_shares[index].value(),
+        })
+    }
+
+    /// Take in the share another participant sent to this one
+    pub fn receive_share(&mut self, message: &DkgShareMessage<G>) -> Result<(), Error> {
+        if message.recipient != self.identifier {
+            return Err(Error::UnknownSigner);
+        }
+        let index = identifier_index(message.sender, self.shares.len())?;
+        receive(&mut self.shares, index, message.share)
+    }
+
+    /// Finish once every participant's commitment message and share are in, failing with `Error::MissingMessages` until then.
+    /// Every share is checked against its sender's commitments, and `Error::InvalidShares` names the participants whose shares are wrong.
+    /// The key share is the sum of the shares received, and the group key the sum of the C_0.
+    pub fn finish(self) -> Result<KeyShare<G>, Error> {
+        let messages = all_received(&self.commitment_messages)?;
+        let shares = all_received(&self.shares)?;
+        let threshold = self.threshold;
+
+        let bad: Vec<usize> = messages
+            .iter()
+            .zip(&shares)
+            .filter(|(message, share)| {
+                feldman_verify(&Share::new(self.identifier, **share), &message.commitments).is_err()
+            })
+            .map(|(message, _)| message.identifier)
+            .collect();
+        if !bad.is_empty() {
+            return Err(Error::InvalidShares(bad));
+        }
+
+        let group_commitments: Vec<G> = (0..threshold)
+            .map(|k| messages.iter().map(|message| message.commitments[k]).sum())
+            .collect();
+        Ok(KeyShare {
+            identifier: self.identifier,
+            signing_share: SecretKey::from_scalar(shares.into_iter().sum())?,
+            public_key_package: PublicKeyPackage::from_commitments(
+                &group_commitments,
+                self.shares.len(),
+            )?,
+        })
+    }
+}
+
+/// Round 1 message of key generation: a participant's commitments C_k = a_k * G to its polynomial, and a proof (R, mu) that it knows a_0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkgCommitmentMessage<G: Group> {
+    identifier: usize,
+    commitments: Vec<G>,
+    proof: (G, G::Scalar),
+}
+
+impl<G: Group + GroupEncoding> DkgCommitmentMessage<G> {
+    /// Returns the identifier of the participant that sent the message
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Returns the commitments C_k to the sender's polynomial
+    pub fn commitments(&self) -> &[G] {
+        &self.commitments
+    }
+
+    /// Returns true if there is one commitment per coefficient and the proof of knowledge mu * G == R + c * C_0 holds
+    fn verify<D: Digest + BlockSizeUser>(&self, threshold: usize, context: &[u8]) -> bool
+    where
+        G: FrostGroup<D>,
+    {
+        let (r_point, mu) = self.proof;
+        self.commitments.len() == threshold
+            && Equation::new(
+                mu,
+                r_point,
+                proof_challenge::<G, D>(context, self.identifier, self.commitments[0], r_point),
+                self.commitments[0],
+            )
+            .holds()
+    }
+}
+
+/// Round 2 message of key generation: the secret share f_i(l) that participant i sends to participant l. It is never printed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DkgShareMessage<G: Group> {
+    sender: usize,
+    recipient: usize,
+    share: G::Scalar,
+}
+
+impl<G: Group> DkgShareMessage<G> {
+    /// Returns the identifier of the participant that sent the share
+    pub fn sender(&self) -> usize {
+        self.sender
+    }
+
+    /// Returns the identifier of the participant the share is for
+    pub fn recipient(&self) -> usize {
+        self.recipient
+    }
+}
+
+impl<G: Group> fmt::Debug for DkgShareMessage<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DkgShareMessage")
+            .field("sender", &self.sender)
+            .field("recipient", &self.recipient)
+            .field("share", &"<redacted>")
+            .finish()
+    }
+}
+
+/// A signer's hiding and binding nonces (d, e) for one signature. It cannot be cloned, `sign` consumes it, and it is zeroized when dropped.
+pub struct SigningNonces<G: Group> {
+    hiding: SecretScalar<G::Scalar>,
+    binding: SecretScalar<G::Scalar>,
+    commitment: SigningCommitment<G>,
+}
+
+impl<G: Group> SigningNonces<G> {
+    /// Returns the commitment to the nonces
+    pub fn commitment(&self) -> SigningCommitment<G> {
+        self.commitment
+    }
+}
+
+impl<G: Group> fmt::Debug for SigningNonces<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SigningNonces(<redacted>)")
+    }
+}
+
+impl<G: Group> ZeroizeOnDrop for SigningNonces<G> {}
+
+/// Round 1 message of signing: a signer's commitments (D, E) = (d * G, e * G) to its hiding and binding nonces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningCommitment<G: Group> {
+    identifier: usize,
+    hiding: G,
+    binding: G,
+}
+
+impl<G: Group> SigningCommitment<G> {
+    /// Returns the identifier of the signer
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Returns the commitment D to the hiding nonce
+    pub fn hiding(&self) -> G {
+        self.hiding
+    }
+
+    /// Returns the commitment E to the binding nonce
+    pub fn binding(&self) -> G {
+        self.binding
+    }
+}
+
+/// The coordinator's choice of signers for one signature: their commitments, ordered by identifier, and the message.
+/// D is the hash function of the ciphersuite, SHA-256 unless another is given.
+#[derive(Debug)]
+pub struct SigningPackage<G: Group, D: Digest = Sha256> {
+    commitments: Vec<SigningCommitment<G>>,
+    message: Vec<u8>,
+    digest: PhantomData<D>,
+}
+
+impl<G: FrostGroup<D>, D: Digest + BlockSizeUser> SigningPackage<G, D> {
+    /// Create a signing package for the message from the commitments of the chosen signers, failing with
+    /// `Error::DuplicateMessage` if a signer has two commitments and with `Error::UnknownSigner` for the identifier 0
+    pub fn new(commitments: &[SigningCommitment<G>], message: &[u8]) -> Result<Self, Error> {
+        let mut commitments = commitments.to_vec();
+        commitments.sort_by_key(SigningCommitment::identifier);
+        if commitments.first().map(SigningCommitment::identifier) == Some(0) {
+            return Err(Error::UnknownSigner);
+        }
+        if commitments
+            .windows(2)
+            .any(|pair| pair[0].identifier == pair[1].identifier)
+        {
+            return Err(Error::DuplicateMessage);
+        }
+        Ok(SigningPackage {
+            commitments,
+            message: message.to_vec(),
+            digest: PhantomData,
+        })
+    }
+
+    /// Returns the signers' commitments, ordered by identifier
+    pub fn commitments(&self) -> &[SigningCommitment<G>] {
+        &self.commitments
+    }
+
+    /// Returns the message to sign
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// Fail with `Error::MissingMessages` if there are fewer signers than the threshold
+    fn check_threshold(&self, public_key_package: &PublicKeyPackage<G>) -> Result<(), Error> {
+        if self.commitments.len() < public_key_package.threshold {
+            return Err(Error::MissingMessages);
+        }
+        Ok(())
+    }
+
+    /// The binding factor rho_i = H1(PK || H4(message) || H5(commitment list) || i) of each signer, which binds its nonces to the message
+    /// and to the commitments of all signers
+    fn binding_factors(&self, group_public_key: PublicKey<G>) -> Vec<G::Scalar> {
+        let mut encoded = Vec::new();
+        for commitment in &self.commitments {
+            encoded.extend_from_slice(&identifier_bytes::<G>(commitment.identifier));
+            encoded.extend_from_slice(commitment.hiding.to_bytes().as_ref());
+            encoded.extend_from_slice(commitment.binding.to_bytes().as_ref());
+        }
+        let pk = group_public_key.point().to_bytes();
+        let message_hash = h4::<G, D>(&self.message);
+        let commitments_hash = h5::<G, D>(&encoded);
+        self.commitments
+            .iter()
+            .map(|commitment| {
+                h1::<G, D>(&[
+                    pk.as_ref(),
+                    &message_hash,
+                    &commitments_hash,
+                    &identifier_bytes::<G>(commitment.identifier),
+                ])
+            })
+            .collect()
+    }
+
+    /// Compute the binding factors, the group commitment R = sum(D_i + rho_i * E_i), the challenge c, and each signer's Lagrange coefficient.
+    /// Fails with `Error::IdentityPoint` if R is the identity, which the signature encoding cannot represent.
+    fn session(&self, group_public_key: PublicKey<G>) -> Result<SigningSession<G>, Error> {
+        let binding_factors = self.binding_factors(group_public_key);
+        let terms: Vec<(G::Scalar, G)> = self
+            .commitments
+            .iter()
+            .zip(&binding_factors)
+            .flat_map(|(commitment, rho)| {
+                [
+                    (G::Scalar::ONE, commitment.hiding),
+                    (*rho, commitment.binding),
+                ]
+            })
+            .collect();
+        let r_point = msm(&terms);
+        if r_point.is_identity().into() {
+            return Err(Error::IdentityPoint);
+        }
+        let c = challenge::<G, D>(r_point, group_public_key, &self.message);
+        let identifiers: Vec<usize> = self
+            .commitments
+            .iter()
+            .map(SigningCommitment::identifier)
+            .collect();
+        // The package was checked for zero and repeated identifiers when it was created
+        let lambdas = identifiers
+            .iter()
+            .map(|identifier| {
+                lagrange_coefficient::<G>(*identifier, &identifiers).expect("invalid identifiers")
+            })
+            .collect();
+        Ok(SigningSession {
+            binding_factors,
+            r_point,
+            c,
+            lambdas,
+        })
+    }
+}
+
+/// The values of a signing package that every signer and the coordinator derive alike
+struct SigningSession<G: Group> {
+    binding_factors: Vec<G::Scalar>,
+    r_point: G,
+    c: G::Scalar,
+    lambdas: Vec<G::Scalar>,
+}
+
+/// Round 2 message of signing: a signer's signature share z_i
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureShare<G: Group> {
+    identifier: usize,
+    z: G::Scalar,
+}
+
+impl<G: Group> SignatureShare<G> {
+    /// Returns the identifier of the signer
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Returns the signature share z_i
+    pub fn z(&self) -> G::Scalar {
+        self.z
+    }
+}
+
+/// Round 2 of signing: compute the signer's share z_i = d_i + e_i * rho_i + lambda_i * s_i * c, consuming its nonces.
+/// Fails with `Error::UnknownSigner` if the package does not hold the commitment to these nonces, with `Error::MissingMessages`
+/// if it has fewer signers than the threshold, and with `Error::IdentityPoint` if the group commitment is the identity.
+pub fn sign<G: FrostGroup<D>, D: Digest + BlockSizeUser>(
+    package: &SigningPackage<G, D>,
+    nonces: SigningNonces<G>,
+    key_share: &KeyShare<G>,
+) -> Result<SignatureShare<G>, Error> {
+    package.check_threshold(&key_share.public_key_package)?;
+    let index = package
+        .commitments
+        .iter()
+        .position(|commitment| *commitment == nonces.commitment)
+        .filter(|_| nonces.commitment.identifier == key_share.identifier)
+        .ok_or(Error::UnknownSigner)?;
+    let session = package.session(key_share.group_public_key())?;
+    let z = nonces.hiding.0
+        + nonces.binding.0 * session.binding_factors[index]
+        + session.lambdas[index] * key_share.signing_share.scalar() * session.c;
+    Ok(SignatureShare {
+        identifier: key_share.identifier,
+        z,
+    })
+}
+
+/// Check every signature share against its signer's commitment and verifying share, z_i * G == D_i + rho_i * E_i + c * lambda_i * Y_i,
+/// and sum them into the signature (z, R). Fails with `Error::MissingMessages` unless every signer of the package sent a share, with
+/// `Error::InvalidPartialSignatures` naming the signers whose share is wrong, and with `Error::IdentityPoint` if the group commitment
+/// R is the identity.
+pub fn aggregate<G: FrostGroup<D>, D: Digest + BlockSizeUser>(
+    package: &SigningPackage<G, D>,
+    signature_shares: &[SignatureShare<G>],
+    public_key_package: &PublicKeyPackage<G>,
+) -> Result<(G::Scalar, G), Error> {
+    package.check_threshold(public_key_package)?;
+    let mut slots = vec![None; package.commitments.len()];
+    for share in signature_shares {
+        let index = package
+            .commitments
+            .iter()
+            .position(|commitment| commitment.identifier == share.identifier)
+            .ok_or(Error::UnknownSigner)?;
+        receive(&mut slots, index, share.z)?;
+    }
+    let zs = all_received(&slots)?;
+
+    let session = package.session(public_key_package.group_public_key)?;
+    let equations = package
+        .commitments
+        .iter()
+        .enumerate()
+        .map(|(i, commitment)| {
+            let verifying_share = public_key_package
+                .verifying_share(commitment.identifier)
+                .ok_or(Error::UnknownSigner)?;
+            Ok(Equation::new(
+                zs[i],
+                commitment.hiding + commitment.binding * session.binding_factors[i],
+                session.c * session.lambdas[i],
+                verifying_share,
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    verify_equations(&equations).map_err(|error| match error {
+        Error::InvalidBatch(indices) => Error::InvalidPartialSignatures(
+            indices
+                .into_iter()
+                .map(|i| package.commitments[i].identifier)
+                .collect(),
+        ),
+        error => error,
+    })?;
+
+    Ok((zs.into_iter().sum(), session.r_point))
+}
+
+/// Verify a signature (z, R) for the message under the group public key: z * G == R + c * PK with the challenge c = H2(R || PK || message).
+/// Fails with `Error::InvalidSignature` otherwise.
+pub fn verify<G: FrostGroup<D>, D: Digest + BlockSizeUser>(
+    signature: (G::Scalar, G),
+    group_public_key: PublicKey<G>,
+    message: &[u8],
+) -> Result<(), Error> {
+    let (z, r_point) = signature;
+    let c = challenge::<G, D>(r_point, group_public_key, message);
+    if !Equation::new(z, r_point, c, group_public_key.point()).holds() {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// The position of a participant in per-participant lists, failing with `Error::UnknownSigner` unless 1 <= identifier <= participants
+fn identifier_index(identifier: usize, participants: usize) -> Result<usize, Error> {
+    if identifier == 0 || identifier > participants {
+        return Err(Error::UnknownSigner);
+    }
+    Ok(identifier - 1)
+}
+
+fn identifier_bytes<G: Group>(identifier: usize) -> Vec<u8> {
+    scalar_to_bytes(&G::Scalar::from(identifier as u64))
+}
+
+/// The signature challenge c = H2(R || PK || message)
+fn challenge<G: FrostGroup<D>, D: Digest + BlockSizeUser>(
+    r_point: G,
+    group_public_key: PublicKey<G>,
+    message: &[u8],
+) -> G::Scalar {
+    h2::<G, D>(&[
+        r_point.to_bytes().as_ref(),
+        group_public_key.point().to_bytes().as_ref(),
+        message,
+    ])
+}
+
+/// The challenge HDKG(i || C_0 || R || context) of a key generation proof of knowledge, binding it to the participant, its commitment C_0
+/// and the run of the key generation
+fn proof_challenge<G: FrostGroup<D>, D: Digest + BlockSizeUser>(
+    context: &[u8],
+    identifier: usize,
+    commitment: G,
+    r_point: G,
+) -> G::Scalar {
+    hash_to_scalar_tagged::<G, D>(
+        "dkg",
+        &[
+            &identifier_bytes::<G>(identifier),
+            commitment.to_bytes().as_ref(),
+            r_point.to_bytes().as_ref(),
+            context,
+        ],
+    )
+}
+
+/// H1 of RFC 9591, for the binding factors
+fn h1<G: FrostGroup<D>, D: Digest + BlockSizeUser>(inputs: &[&[u8]]) -> G::Scalar {
+    hash_to_scalar_tagged::<G, D>("rho", inputs)
+}
+
+/// H2 of RFC 9591, for the challenge
+fn h2<G: FrostGroup<D>, D: Digest + BlockSizeUser>(inputs: &[&[u8]]) -> G::Scalar {
+    hash_to_scalar_tagged::<G, D>("chal", inputs)
+}
+
+/// H3 of RFC 9591, for the nonces
+fn h3<G: FrostGroup<D>, D: Digest + BlockSizeUser>(inputs: &[&[u8]]) -> G::Scalar {
+    hash_to_scalar_tagged::<G, D>("nonce", inputs)
+}
+
+/// H4 of RFC 9591, for the message in the binding factors
+fn h4<G: FrostGroup<D>, D: Digest + BlockSizeUser>(message: &[u8]) -> Output<D> {
+    D::new()
+        .chain_update(G::CONTEXT_STRING)
+        .chain_update("msg")
+        .chain_update(message)
+        .finalize()
+}
+
+/// H5 of RFC 9591, for the commitment list in the binding factors
+fn h5<G: FrostGroup<D>, D: Digest + BlockSizeUser>(encoded_commitments: &[u8]) -> Output<D> {
+    D::new()
+        .chain_update(G::CONTEXT_STRING)
+        .chain_update("com")
+        .chain_update(encoded_commitments)
+        .finalize()
+}
+
+/// hash_to_field under the domain separation tag contextString || tag
+fn hash_to_scalar_tagged<G: FrostGroup<D>, D: Digest + BlockSizeUser>(
+    tag: &str,
+    inputs: &[&[u8]],
+) -> G::Scalar {
+    let dst = [G::CONTEXT_STRING.as_bytes(), tag.as_bytes()].concat();
+    hash_to_field::<G::Scalar, D>(&dst, inputs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bytes_from_hex, point_from_bytes, scalar_from_bytes, sharing::reconstruct};
+    use k256::ProjectivePoint;
+    use rand::{seq::SliceRandom, Rng};
+
+    /// Run both signing rounds with the given key shares and return the signature
+    fn sign_with<G: FrostGroup>(
+        key_shares: &[&KeyShare<G>],
+        message: &str,
+    ) -> Result<(G::Scalar, G), Error> {
+        let (nonces, commitments): (Vec<_>, Vec<_>) = key_shares
+            .iter()
+            .map(|key_share| key_share.commit::<Sha256>())
+            .unzip();
+        let package = SigningPackage::<G>::new(&commitments, message.as_bytes())?;
+        let signature_shares = nonces
+            .into_iter()
+            .zip(key_shares)
+            .map(|(nonces, key_share)| sign(&package, nonces, key_share))
+            .collect::<Result<Vec<_>, _>>()?;
+        aggregate(
+            &package,
+            &signature_shares,
+            key_shares[0].public_key_package(),
+        )
+    }
+
+    /// A random choice of `count` of the key shares
+    fn choose<G: Group>(key_shares: &[KeyShare<G>], count: usize) -> Vec<&KeyShare<G>> {
+        key_shares
+            .choose_multiple(&mut rand::thread_rng(), count)
+            .collect()
+    }
+
+    /// Run the distributed key generation between all participants
+    fn run_dkg(
+        threshold: usize,
+        participants: usize,
+    ) -> Result<Vec<KeyShare<ProjectivePoint>>, Error> {
+        let (mut dkg, messages): (Vec<_>, Vec<_>) = (1..=participants)
+            .map(|identifier| {
+                DkgParticipant::<ProjectivePoint>::new(identifier, threshold, participants, b"run")
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+        for message in &messages {
+            for participant in dkg.iter_mut() {
+                if participant.identifier() != message.identifier() {
+                    participant.receive_commitment(message)?;
+                }
+            }
+        }
+        let shares = dkg
+            .iter()
+            .map(|sender| {
+                (1..=participants)
+                    .filter(|recipient| *recipient != sender.identifier())
+                    .map(|recipient| sender.share_for(recipient))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for share in shares.iter().flatten() {
+            dkg[share.recipient() - 1].receive_share(share)?;
+        }
+        dkg.into_iter().map(DkgParticipant::finish).collect()
+    }
+
+    #[test]
+    fn frost_test_trusted_dealer() {
+        for (threshold, participants) in [(1, 1), (1, 3), (2, 3), (3, 5), (5, 5)] {
+            let secret_key = SecretKey::<ProjectivePoint>::random();
+            let (key_shares, commitments) =
+                trusted_dealer_keygen(&secret_key, threshold, participants).unwrap();
+            assert_eq!(key_shares.len(), participants);
+            for key_share in &key_shares {
+                assert!(key_share.verify(&commitments).is_ok());
+                assert_eq!(key_share.group_public_key(), secret_key.public_key());
+            }
+
+            let message = "threshold";
+            let signers = rand::thread_rng().gen_range(threshold..=participants);
+            let signature = sign_with(&choose(&key_shares, signers), message).unwrap();
+            let pk = secret_key.public_key();
+            assert!(verify(signature, pk, message.as_bytes()).is_ok());
+            assert_eq!(
+                verify(signature, pk, b"other"),
+                Err(Error::InvalidSignature)
+            );
+        }
+    }
+
+    /// Test that a tampered share from the dealer fails to verify against the commitments
+    #[test]
+    fn frost_test_dealer_share_verification() {
+        let (mut key_shares, commitments) =
+            trusted_dealer_keygen(&SecretKey::<ProjectivePoint>::random(), 2, 3).unwrap();
+        key_shares[1].signing_share = SecretKey::random();
+        assert_eq!(
+            key_shares[1].verify(&commitments),
+            Err(Error::InvalidShares(vec![2]))
+        );
+        assert_eq!(
+            key_shares[0].verify(&commitments[1..]),
+            Err(Error::InvalidShares(vec![1]))
+        );
+    }
+
+    #[test]
+    fn frost_test_dkg() {
+        for (threshold, participants) in [(1, 2), (2, 3), (3, 5)] {
+            let key_shares = run_dkg(threshold, participants).unwrap();
+            let package = key_shares[0].public_key_package();
+            for key_share in &key_shares {
+                assert_eq!(key_share.public_key_package(), package);
+                assert_eq!(
+                    package.verifying_share(key_share.identifier()),
+                    Some(key_share.signing_share().public_key().point())
+                );
+            }
+
+            // Any threshold of the shares interpolate to the group secret key
+            let chosen = choose(&key_shares, threshold);
+            let shares: Vec<_> = chosen
+                .iter()
+                .map(|key_share| {
+                    Share::<ProjectivePoint>::new(
+                        key_share.identifier(),
+                        *key_share.signing_share().scalar(),
+                    )
+                })
+                .collect();
+            let secret = reconstruct(&shares).unwrap();
+            assert_eq!(
+                ProjectivePoint::GENERATOR * secret,
+                package.group_public_key().point()
+            );
+
+            let signature = sign_with(&choose(&key_shares, threshold), "dkg").unwrap();
+            assert!(verify(signature, package.group_public_key(), b"dkg").is_ok());
+        }
+    }
+
+    /// Test that key generation names the participants that sent a bad share or a bad proof of knowledge
+    #[test]
+    fn frost_test_dkg_blame() {
+        let (mut first, _) = DkgParticipant::<ProjectivePoint>::new(1, 2, 3, b"run").unwrap();
+        let (second, second_message) =
+            DkgParticipant::<ProjectivePoint>::new(2, 2, 3, b"run").unwrap();
+        let (third, mut third_message) =
+            DkgParticipant::<ProjectivePoint>::new(3, 2, 3, b"run").unwrap();
+        first.receive_commitment(&second_message).unwrap();
+        let valid_third_message = third_message.clone();
+        third_message.proof.1 += <ProjectivePoint as Group>::Scalar::ONE;
+        assert_eq!(
+            first.receive_commitment(&third_message),
+            Err(Error::InvalidShares(vec![3]))
+        );
+        first.receive_commitment(&valid_third_message).unwrap();
+
+        let mut bad_share = second.share_for(1).unwrap();
+        bad_share.share += <ProjectivePoint as Group>::Scalar::ONE;
+        first.receive_share(&bad_share).unwrap();
+        assert_eq!(
+            first.receive_share(&bad_share),
+            Err(Error::DuplicateMessage)
+        );
+        assert_eq!(
+            first.receive_share(&third.share_for(2).unwrap()),
+            Err(Error::UnknownSigner)
+        );
+        first.receive_share(&third.share_for(1).unwrap()).unwrap();
+        assert_eq!(first.finish().unwrap_err(), Error::InvalidShares(vec![2]));
+    }
+
+    /// Test that a proof of knowledge from another run of the key generation is rejected
+    #[test]
+    fn frost_test_dkg_context() {
+        let (mut first, _) = DkgParticipant::<ProjectivePoint>::new(1, 2, 2, b"run").unwrap();
+        let (_, replayed) = DkgParticipant::<ProjectivePoint>::new(2, 2, 2, b"other run").unwrap();
+        assert_eq!(
+            first.receive_commitment(&replayed),
+            Err(Error::InvalidShares(vec![2]))
+        );
+        let (_, fresh) = DkgParticipant::<ProjectivePoint>::new(2, 2, 2, b"run").unwrap();
+        assert!(first.receive_commitment(&fresh).is_ok());
+    }
+
+    /// Test that the aggregator names the signers whose signature share is wrong
+    #[test]
+    fn frost_test_blame() {
+        let (key_shares, _) =
+            trusted_dealer_keygen(&SecretKey::<ProjectivePoint>::random(), 3, 5).unwrap();
+        let signers = choose(&key_shares, 4);
+        let (nonces, commitments): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .map(|key_share| key_share.commit::<Sha256>())
+            .unzip();
+        let package = SigningPackage::<ProjectivePoint>::new(&commitments, b"blame").unwrap();
+        let mut signature_shares: Vec<_> = nonces
+            .into_iter()
+            .zip(&signers)
+            .map(|(nonces, key_share)| sign(&package, nonces, key_share).unwrap())
+            .collect();
+        signature_shares[1].z += <ProjectivePoint as Group>::Scalar::ONE;
+        signature_shares[3].z = <ProjectivePoint as Group>::Scalar::ZERO;
+
+        let mut cheaters = vec![
+            signature_shares[1].identifier,
+            signature_shares[3].identifier,
+        ];
+        cheaters.sort();
+        assert_eq!(
+            aggregate(
+                &package,
+                &signature_shares,
+                key_shares[0].public_key_package()
+            ),
+            Err(Error::InvalidPartialSignatures(cheaters))
+        );
+        assert_eq!(
+            aggregate(
+                &package,
+                &signature_shares[1..],
+                key_shares[0].public_key_package()
+            ),
+            Err(Error::MissingMessages)
+        );
+    }
+
+    #[test]
+    fn frost_test_errors() {
+        let secret_key = SecretKey::<ProjectivePoint>::random();
+        assert_eq!(
+            trusted_dealer_keygen(&secret_key, 0, 3).unwrap_err(),
+            Error::InvalidThreshold
+        );
+        assert_eq!(
+            trusted_dealer_keygen(&secret_key, 4, 3).unwrap_err(),
+            Error::InvalidThreshold
+        );
+        assert_eq!(
+            DkgParticipant::<ProjectivePoint>::new(4, 2, 3, b"run").unwrap_err(),
+            Error::UnknownSigner
+        );
+
+        let (key_shares, _) = trusted_dealer_keygen(&secret_key, 2, 3).unwrap();
+        let (nonces, commitment) = key_shares[0].commit::<Sha256>();
+        let (_, other_commitment) = key_shares[1].commit::<Sha256>();
+        assert_eq!(
+            SigningPackage::<ProjectivePoint>::new(&[commitment, commitment], b"twice")
+                .unwrap_err(),
+            Error::DuplicateMessage
+        );
+
+        // Too few signers
+        let package = SigningPackage::<ProjectivePoint>::new(&[commitment], b"alone").unwrap();
+        assert_eq!(
+            sign(&package, nonces, &key_shares[0]).unwrap_err(),
+            Error::MissingMessages
+        );
+
+        // A package that does not hold the commitment to the signer's nonces
+        let (nonces, _) = key_shares[0].commit::<Sha256>();
+        let package =
+            SigningPackage::<ProjectivePoint>::new(&[commitment, other_commitment], b"stale")
+                .unwrap();
+        assert_eq!(
+            sign(&package, nonces, &key_shares[0]).unwrap_err(),
+            Error::UnknownSigner
+        );
+        assert_eq!(
+            format!("{:?}", key_shares[0].commit::<Sha256>().0),
+            "SigningNonces(<redacted>)"
+        );
+    }
+
+    /// Test that the aggregator rejects a group commitment R that is the identity
+    #[test]
+    fn frost_test_identity_commitment() {
+        let (key_shares, _) =
+            trusted_dealer_keygen(&SecretKey::<ProjectivePoint>::random(), 2, 2).unwrap();
+        let commitments: Vec<_> = key_shares
+            .iter()
+            .map(|key_share| SigningCommitment {
+                identifier: key_share.identifier(),
+                hiding: ProjectivePoint::IDENTITY,
+                binding: ProjectivePoint::IDENTITY,
+            })
+            .collect();
+        let package = SigningPackage::<ProjectivePoint>::new(&commitments, b"identity").unwrap();
+        let signature_shares: Vec<_> = key_shares
+            .iter()
+            .map(|key_share| SignatureShare {
+                identifier: key_share.identifier(),
+                z: <ProjectivePoint as Group>::Scalar::ONE,
+            })
+            .collect();
+        assert_eq!(
+            aggregate(
+                &package,
+                &signature_shares,
+                key_shares[0].public_key_package()
+            ),
+            Err(Error::IdentityPoint)
+        );
+    }
+
+    #[test]
+    fn frost_test_p256() {
+        let secret_key = SecretKey::<p256::ProjectivePoint>::random();
+        let (key_shares, _) = trusted_dealer_keygen(&secret_key, 2, 3).unwrap();
+        let signature = sign_with(&choose(&key_shares, 2), "p256").unwrap();
+        assert!(verify(signature, secret_key.public_key(), b"p256").is_ok());
+    }
+
+    /// The FROST(secp256k1, SHA-256) test vectors of RFC 9591, appendix E.5
+    #[test]
+    fn frost_test_rfc9591_vectors() {
+        type Scalar = <ProjectivePoint as Group>::Scalar;
+        let scalar = |hex: &str| scalar_from_bytes::<Scalar>(&bytes_from_hex(hex)).unwrap();
+        let point = |hex: &str| point_from_bytes::<ProjectivePoint>(&bytes_from_hex(hex)).unwrap();
+
+        let group_secret_key =
+            scalar("0d004150d27c3bf2a42f312683d35fac7394b1e9e318249c1bfe7f0795a83114");
+        let coefficient =
+            scalar("fbf85eadae3058ea14f19148bb72b45e4399c0b16028acaf0395c9b03c823579");
+        let public_key_package = PublicKeyPackage::from_commitments(
+            &[
+                ProjectivePoint::GENERATOR * group_secret_key,
+                ProjectivePoint::GENERATOR * coefficient,
+            ],
+            3,
+        )
+        .unwrap();
+        assert_eq!(
+            public_key_package.group_public_key().point(),
+            point("02f37c34b66ced1fb51c34a90bdae006901f10625cc06c4f64663b0eae87d87b4f")
+        );
+        let message = bytes_from_hex("74657374");
+
+        // Participants 1 and 3 sign: share, hiding and binding nonce randomness, D, E, rho and z
+        let signers = [
+            (
+                1,
+                "08f89ffe80ac94dcb920c26f3f46140bfc7f95b493f8310f5fc1ea2b01f4254c",
+                "7ea5ed09af19f6ff21040c07ec2d2adbd35b759da5a401d4c99dd26b82391cb2",
+                "47acab018f116020c10cb9b9abdc7ac10aae1b48ca6e36dc15acb6ec9be5cdc5",
+                "03c699af97d26bb4d3f05232ec5e1938c12f1e6ae97643c8f8f11c9820303f1904",
+                "02fa2aaccd51b948c9dc1a325d77226e98a5a3fe65fe9ba213761a60123040a45e",
+                "3e08fe561e075c653cbfd46908a10e7637c70c74f0a77d5fd45d1a750c739ec6",
+                "c4fce1775a1e141fb579944166eab0d65eefe7b98d480a569bbbfcb14f91c197",
+            ),
+            (
+                3,
+                "00e95d59dd0d46b0e303e500b62b7ccb0e555d49f5b849f5e748c071da8c0dbc",
+                "e6cc56ccbd0502b3f6f831d91e2ebd01c4de0479e0191b66895a4ffd9b68d544",
+                "7203d55eb82a5ca0d7d83674541ab55f6e76f1b85391d2c13706a89a064fd5b9",
+                "03077507ba327fc074d2793955ef3410ee3f03b82b4cdc2370f71d865beb926ef6",
+                "02ad53031ddfbbacfc5fbda3d3b0c2445c8e3e99cbc4ca2db2aa283fa68525b135",
+                "93f79041bb3fd266105be251adaeb5fd7f8b104fb554a4ba9a0becea48ddbfd7",
+                "0160fd0d388932f4826d2ebcd6b9eaba734f7c71cf25b4279a4ca2581e47b18d",
+            ),
+        ];
+        let mut key_shares = Vec::new();
+        let mut nonces = Vec::new();
+        let mut commitments = Vec::new();
+        for (identifier, share, hiding_randomness, binding_randomness, hiding, binding, _, _) in
+            signers
+        {
+            let key_share = KeyShare {
+                identifier,
+                signing_share: SecretKey::from_scalar(scalar(share)).unwrap(),
+                public_key_package: public_key_package.clone(),
+            };
+            assert!(key_share
+                .verify(&[
+                    ProjectivePoint::GENERATOR * group_secret_key,
+                    ProjectivePoint::GENERATOR * coefficient
+                ])
+                .is_ok());
+            let (signing_nonces, commitment) = key_share.commit_with_randomness::<Sha256>(
+                &bytes_from_hex(hiding_randomness),
+                &bytes_from_hex(binding_randomness),
+            );
+            assert_eq!(commitment.hiding(), point(hiding));
+            assert_eq!(commitment.binding(), point(binding));
+            key_shares.push(key_share);
+            nonces.push(signing_nonces);
+            commitments.push(commitment);
+        }
+
+        let package = SigningPackage::<ProjectivePoint>::new(&commitments, &message).unwrap();
+        let session = package
+            .session(public_key_package.group_public_key())
+            .unwrap();
+        let signature_shares: Vec<_> = nonces
+            .into_iter()
+            .zip(&key_shares)
+            .map(|(nonces, key_share)| sign(&package, nonces, key_share).unwrap())
+            .collect();
+        for (i, (.., rho, z)) in signers.iter().enumerate() {
+            assert_eq!(session.binding_factors[i], scalar(rho));
+            assert_eq!(signature_shares[i].z(), scalar(z));
+        }
+
+        let (z, r_point) = aggregate(&package, &signature_shares, &public_key_package).unwrap();
+        let encoded = [r_point.to_bytes().as_ref(), scalar_to_bytes(&z).as_slice()].concat();
+        assert_eq!(
+            encoded,
+            bytes_from_hex(
+                "0205b6d04d3774c8929413e3c76024d54149c372d57aae62574ed74319b5ea14d0c65dde8492a7471437e6c2fe3da49b90d23f642b5c6dbe7e36089f096dd97324"
+            )
+        );
+        assert!(verify(
+            (z, r_point),
+            public_key_package.group_public_key(),
+            &message
+        )
+        .is_ok());
+    }
+}
@@ -0,0 +1,217 @@
+use elliptic_curve::{Field, Group};
+use rand::{CryptoRng, RngCore};
+use std::{
+    fmt, ptr,
+    sync::atomic::{compiler_fence, Ordering},
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::Error;
+
+/// A secret key: a nonzero scalar. The scalar is overwritten with zero when the key is dropped, and is never printed.
+pub struct SecretKey<G: Group> {
+    scalar: SecretScalar<G::Scalar>,
+}
+
+/// A scalar that is overwritten with zero when it is dropped, which `Group::Scalar` alone does not guarantee.
+/// It is not `Copy`, so that every copy of it is zeroized too.
+#[derive(Clone)]
+pub(crate) struct SecretScalar<F: Field>(pub(crate) F);
+
+impl<F: Field> Zeroize for SecretScalar<F> {
+    fn zeroize(&mut self) {
+        // A volatile write followed by a fence, as the zeroize crate does, so that the compiler cannot elide the store
+        // SAFETY: the pointer comes from a mutable reference, so it is valid and aligned, and F is Copy, so nothing is leaked
+        unsafe { ptr::write_volatile(&mut self.0, F::ZERO) };
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<F: Field> Drop for SecretScalar<F> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<F: Field> ZeroizeOnDrop for SecretScalar<F> {}
+
+impl<G: Group> SecretKey<G> {
+    /// Generate a random secret key
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Generate a random secret key, drawing randomness from the given RNG
+    pub fn random_with_rng(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        loop {
+            // Zero is drawn with negligible probability, but is not a valid key
+            if let Ok(secret_key) = Self::from_scalar(G::Scalar::random(&mut *rng)) {
+                return secret_key;
+            }
+        }
+    }
+
+    /// Create a secret key from a scalar, failing with `Error::ZeroScalar` if it is zero
+    pub fn from_scalar(scalar: G::Scalar) -> Result<Self, Error> {
+        if bool::from(scalar.is_zero()) {
+            return Err(Error::ZeroScalar);
+        }
+        Ok(SecretKey {
+            scalar: SecretScalar(scalar),
+        })
+    }
+
+    /// Returns the secret scalar. Copies of it are not zeroized when the key is dropped, so prefer computing with the reference.
+    pub fn scalar(&self) -> &G::Scalar {
+        &self.scalar.0
+    }
+
+    /// Compute the public key sk * generator
+    pub fn public_key(&self) -> PublicKey<G> {
+        // Neither factor is zero and the group has prime order, so this is never the identity
+        PublicKey {
+            point: G::generator() * self.scalar(),
+        }
+    }
+}
+
+impl<G: Group> Clone for SecretKey<G> {
+    fn clone(&self) -> Self {
+        SecretKey {
+            scalar: self.scalar.clone(),
+        }
+    }
+}
+
+impl<G: Group> fmt::Debug for SecretKey<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(<redacted>)")
+    }
+}
+
+/// The scalar zeroizes itself when the key is dropped
+impl<G: Group> ZeroizeOnDrop for SecretKey<G> {}
+
+/// A public key: a point that is not the identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey<G: Group> {
+    point: G,
+}
+
+impl<G: Group> PublicKey<G> {
+    /// Create a public key from a point, failing with `Error::IdentityPoint` if it is the identity
+    pub fn from_point(point: G) -> Result<Self, Error> {
+        if point == G::identity() {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(PublicKey { point })
+    }
+
+    /// Returns the public point
+    pub fn point(&self) -> G {
+        self.point
+    }
+}
+
+/// A secret key together with its public key
+#[derive(Debug, Clone)]
+pub struct KeyPair<G: Group> {
+    secret_key: SecretKey<G>,
+    public_key: PublicKey<G>,
+}
+
+impl<G: Group> KeyPair<G> {
+    /// Generate a random key pair
+    pub fn random() -> Self {
+        Self::from_secret_key(SecretKey::random())
+    }
+
+    /// Generate a random key pair, drawing randomness from the given RNG
+    pub fn random_with_rng(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self::from_secret_key(SecretKey::random_with_rng(rng))
+    }
+
+    /// Create a key pair from a secret key, computing the public key
+    pub fn from_secret_key(secret_key: SecretKey<G>) -> Self {
+        let public_key = secret_key.public_key();
+        KeyPair {
+            secret_key,
+            public_key,
+        }
+    }
+
+    /// Returns the secret key
+    pub fn secret_key(&self) -> &SecretKey<G> {
+        &self.secret_key
+    }
+
+    /// Returns the public key
+    pub fn public_key(&self) -> PublicKey<G> {
+        self.public_key
+    }
+}
+
+impl<G: Group> From<SecretKey<G>> for KeyPair<G> {
+    fn from(secret_key: SecretKey<G>) -> Self {
+        Self::from_secret_key(secret_key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k256::{ProjectivePoint, Scalar};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn keys_reject_invalid() {
+        assert_eq!(
+            SecretKey::<ProjectivePoint>::from_scalar(Scalar::ZERO).unwrap_err(),
+            Error::ZeroScalar
+        );
+        assert_eq!(
+            PublicKey::from_point(ProjectivePoint::IDENTITY),
+            Err(Error::IdentityPoint)
+        );
+    }
+
+    #[test]
+    fn key_pair_test() {
+        for _ in 1..100 {
+            let key_pair = KeyPair::<ProjectivePoint>::random();
+            let pk = ProjectivePoint::GENERATOR * key_pair.secret_key().scalar();
+            assert_eq!(key_pair.public_key().point(), pk);
+            assert_eq!(key_pair.secret_key().public_key(), key_pair.public_key());
+        }
+    }
+
+    #[test]
+    fn secret_key_debug_is_redacted() {
+        let secret_key =
+            SecretKey::<ProjectivePoint>::from_scalar(Scalar::from(0x1234u64)).unwrap();
+        let key_pair = KeyPair::from(secret_key.clone());
+        assert_eq!(format!("{:?}", secret_key), "SecretKey(<redacted>)");
+        assert!(!format!("{:?}", key_pair).contains("1234"));
+    }
+
+    #[test]
+    fn secret_scalar_zeroize() {
+        let mut scalar = SecretScalar(Scalar::from(0x1234u64));
+        let copy = scalar.clone();
+        scalar.zeroize();
+        assert_eq!(scalar.0, Scalar::ZERO);
+        assert_eq!(copy.0, Scalar::from(0x1234u64));
+    }
+
+    #[test]
+    fn key_pair_seeded_rng() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let first = KeyPair::<ProjectivePoint>::random_with_rng(&mut rng);
+        let second = KeyPair::<ProjectivePoint>::random_with_rng(&mut rng);
+        assert_ne!(first.public_key(), second.public_key());
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let again = KeyPair::<ProjectivePoint>::random_with_rng(&mut rng);
+        assert_eq!(first.public_key(), again.public_key());
+    }
+}
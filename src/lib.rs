@@ -1,30 +1,195 @@
-use elliptic_curve::{generic_array::GenericArray, Field, Group};
-use sha2::digest::generic_array::typenum::U32;
-use sha2::{Digest, Sha256};
+use elliptic_curve::{group::GroupEncoding, Group, PrimeField};
+use sha2::{
+    digest::{core_api::BlockSizeUser, Output},
+    Digest,
+};
 
-mod musig;
+pub mod bip340;
+pub mod ecdh;
+pub mod ecdsa;
+pub mod ecies;
+pub mod error;
+pub mod frost;
+pub mod keys;
+pub mod msm;
+pub mod musig;
+mod rfc6979;
+pub mod schnorr;
+pub mod sharing;
 
-pub fn hash<T: Group>(inputs: Vec<&[u8]>) -> T::Scalar {
-    let mut hasher = Sha256::new();
+pub use error::Error;
+pub use keys::{KeyPair, PublicKey, SecretKey};
+
+/// Hash the concatenation of the inputs with the digest D and reduce the result to a scalar
+pub fn hash<T: Group, D: Digest>(inputs: Vec<&[u8]>) -> T::Scalar {
+    let mut hasher = D::new();
     for input in inputs {
         hasher.update(input)
     }
-    let hash = hasher.finalize();
-    let mut scalar = <T::Scalar as Field>::ZERO;
-    let scalar256 = <T::Scalar as From<u64>>::from(256);
-    for byte in hash {
-        scalar *= scalar256; // TODO: Maybe do this by doubling?
-        scalar += <T::Scalar as From<u64>>::from(byte as u64)
+    hash_to_scalar::<T::Scalar>(&hasher.finalize())
+}
+
+/// The tagged hash D(D(tag) || D(tag) || inputs) of BIP-340, which keeps hashes for different purposes apart
+fn tagged_hash<D: Digest>(tag: &str, inputs: &[&[u8]]) -> Output<D> {
+    let tag_hash = D::digest(tag.as_bytes());
+    let mut hasher = D::new();
+    hasher.update(&tag_hash);
+    hasher.update(&tag_hash);
+    for input in inputs {
+        hasher.update(input);
     }
-    scalar
+    hasher.finalize()
 }
 
-fn hash_to_scalar<T: Group>(hash: GenericArray<u8, U32>) -> T::Scalar {
-    let mut scalar = <T::Scalar as Field>::ZERO;
-    let scalar256 = <T::Scalar as From<u64>>::from(256);
+/// hash_to_field of RFC 9380 for one element of a prime field: expand_message_xmd with the digest D to ceil((NUM_BITS + 128) / 8)
+/// bytes under the domain separation tag, read as a big-endian integer and reduced. This is how RFC 9591 hashes to scalars for the
+/// secp256k1 and P-256 ciphersuites of FROST.
+fn hash_to_field<F: PrimeField, D: Digest + BlockSizeUser>(dst: &[u8], inputs: &[&[u8]]) -> F {
+    let len = (F::NUM_BITS as usize + 128).div_ceil(8);
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let mut hasher = D::new();
+    hasher.update(vec![0u8; D::block_size()]);
+    for input in inputs {
+        hasher.update(input);
+    }
+    hasher.update((len as u16).to_be_bytes());
+    hasher.update([0u8]);
+    hasher.update(&dst_prime);
+    let b_0 = hasher.finalize();
+
+    let mut b_i = D::new()
+        .chain_update(&b_0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut uniform_bytes = b_i.to_vec();
+    for i in 2..=len.div_ceil(<D as Digest>::output_size()) {
+        let xored: Vec<u8> = b_0.iter().zip(&b_i).map(|(x, y)| x ^ y).collect();
+        b_i = D::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    hash_to_scalar(&uniform_bytes[..len])
+}
+
+/// Interpret bytes as a big-endian integer and reduce it modulo the order of the scalar field
+fn hash_to_scalar<F: PrimeField>(hash: &[u8]) -> F {
+    let mut scalar = F::ZERO;
+    let scalar256 = F::from(256);
     for byte in hash {
         scalar *= scalar256; // TODO: Maybe do this by doubling?
-        scalar += <T::Scalar as From<u64>>::from(byte as u64)
+        scalar += F::from(*byte as u64)
     }
     scalar
 }
+
+/// Encode a scalar as a big-endian integer of `ceil(NUM_BITS / 8)` bytes.
+/// This does not depend on the byte order `PrimeField::to_repr` happens to use for a given curve, and takes the same time for every scalar.
+fn scalar_to_bytes<F: PrimeField>(scalar: &F) -> Vec<u8> {
+    let len = (F::NUM_BITS as usize).div_ceil(8);
+    let mut bytes = scalar.to_repr().as_ref().to_vec();
+    // The representation of one tells the byte order apart without looking at the (possibly secret) scalar
+    if F::ONE.to_repr().as_ref()[0] == 1 {
+        bytes.reverse();
+    }
+    bytes.split_off(bytes.len() - len)
+}
+
+/// Decode a big-endian scalar of `ceil(NUM_BITS / 8)` bytes, failing with `Error::InvalidEncoding` if the length is wrong or it is not less than the group order
+fn scalar_from_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, Error> {
+    let modulus = modulus_bytes::<F>();
+    if bytes.len() != modulus.len() || bytes >= modulus.as_slice() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(hash_to_scalar(bytes))
+}
+
+/// Decode a point from its `GroupEncoding` bytes, failing with `Error::InvalidEncoding` if the length is wrong or the bytes are not a point
+fn point_from_bytes<G: GroupEncoding>(bytes: &[u8]) -> Result<G, Error> {
+    let mut repr = G::Repr::default();
+    if repr.as_ref().len() != bytes.len() {
+        return Err(Error::InvalidEncoding);
+    }
+    repr.as_mut().copy_from_slice(bytes);
+    Option::from(G::from_bytes(&repr)).ok_or(Error::InvalidEncoding)
+}
+
+/// Store a value received from the party at `index`, failing with `Error::UnknownSigner` for an index past the end of the list of parties
+/// and with `Error::DuplicateMessage` if that party already sent one
+fn receive<T>(slots: &mut [Option<T>], index: usize, value: T) -> Result<(), Error> {
+    let slot = slots.get_mut(index).ok_or(Error::UnknownSigner)?;
+    if slot.is_some() {
+        return Err(Error::DuplicateMessage);
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// Returns the received values once every party has sent one, or `Error::MissingMessages`
+fn all_received<T: Clone>(slots: &[Option<T>]) -> Result<Vec<T>, Error> {
+    slots
+        .iter()
+        .cloned()
+        .collect::<Option<_>>()
+        .ok_or(Error::MissingMessages)
+}
+
+/// The order of the scalar field as a big-endian integer of `ceil(NUM_BITS / 8)` bytes
+fn modulus_bytes<F: PrimeField>() -> Vec<u8> {
+    let len = (F::NUM_BITS as usize).div_ceil(8);
+    let hex = F::MODULUS.trim_start_matches("0x");
+    // Left pad to an even number of digits so that every byte is two digits
+    let hex = format!("{:0>width$}", hex, width = 2 * len);
+    (0..len)
+        .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).expect("invalid modulus"))
+        .collect()
+}
+
+/// Parse a hex string into bytes, for the test vectors
+#[cfg(test)]
+fn bytes_from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use elliptic_curve::Field;
+    use k256::{ProjectivePoint, Scalar};
+
+    #[test]
+    fn scalar_bytes_round_trip() {
+        for _ in 1..100 {
+            let scalar = <Scalar as Field>::random(rand::thread_rng());
+            let bytes = scalar_to_bytes(&scalar);
+            // k256 uses big-endian encoding for scalars
+            assert_eq!(bytes.as_slice(), scalar.to_repr().as_slice());
+            assert_eq!(hash_to_scalar::<Scalar>(&bytes), scalar);
+        }
+        for _ in 1..100 {
+            let scalar = <p224::Scalar as Field>::random(rand::thread_rng());
+            let bytes = scalar_to_bytes(&scalar);
+            assert_eq!(bytes.len(), 28);
+            assert_eq!(hash_to_scalar::<p224::Scalar>(&bytes), scalar);
+        }
+        assert_eq!(
+            scalar_to_bytes(&Scalar::ONE),
+            [&[0u8; 31][..], &[1]].concat()
+        );
+    }
+
+    #[test]
+    fn modulus_bytes_test() {
+        let modulus = modulus_bytes::<<ProjectivePoint as Group>::Scalar>();
+        let minus_one = scalar_to_bytes(&-Scalar::ONE);
+        assert_eq!(modulus.len(), 32);
+        assert_eq!(modulus[..31], minus_one[..31]);
+        assert_eq!(modulus[31], minus_one[31] + 1);
+    }
+}
@@ -0,0 +1,242 @@
+use elliptic_curve::{Group, PrimeField};
+
+use crate::scalar_to_bytes;
+
+// Multi-scalar multiplication: compute sum(s_i * P_i) faster than adding up separate scalar multiplications.
+// Straus' method shares the doublings between all terms; Pippenger's bucket method also shares the additions and wins for many terms.
+
+/// Above this many terms `msm` switches from Straus to Pippenger
+const STRAUS_MAX_TERMS: usize = 256;
+
+/// Width of the wNAF digits used by Straus: each point needs a table of 2^(w-2) odd multiples
+const WNAF_WIDTH: usize = 5;
+
+/// Compute sum(s_i * P_i) over all (s_i, P_i), choosing the faster algorithm for the number of terms.
+/// The running time depends on the scalars, so they must be public, as in signature verification.
+pub fn msm<G: Group>(terms: &[(G::Scalar, G)]) -> G {
+    if terms.len() <= STRAUS_MAX_TERMS {
+        straus(terms)
+    } else {
+        pippenger(terms)
+    }
+}
+
+/// Straus' method with interleaved width-w NAFs: one shared chain of doublings, and an addition for each nonzero digit of each scalar
+pub fn straus<G: Group>(terms: &[(G::Scalar, G)]) -> G {
+    // Odd multiples P, 3P, ..., (2^(w-1) - 1)P of each point
+    let tables: Vec<Vec<G>> = terms
+        .iter()
+        .map(|(_, point)| {
+            let double = point.double();
+            let mut table = vec![*point];
+            for i in 1..1 << (WNAF_WIDTH - 2) {
+                table.push(table[i - 1] + double);
+            }
+            table
+        })
+        .collect();
+    let nafs: Vec<Vec<i64>> = terms
+        .iter()
+        .map(|(scalar, _)| wnaf(&limbs(scalar), WNAF_WIDTH))
+        .collect();
+
+    let len = nafs.iter().map(Vec::len).max().unwrap_or(0);
+    let mut acc = G::identity();
+    for i in (0..len).rev() {
+        acc = acc.double();
+        for (naf, table) in nafs.iter().zip(&tables) {
+            match naf.get(i) {
+                Some(&digit) if digit > 0 => acc += table[(digit / 2) as usize],
+                Some(&digit) if digit < 0 => acc -= table[(-digit / 2) as usize],
+                _ => {}
+            }
+        }
+    }
+    acc
+}
+
+/// Pippenger's bucket method: for each c-bit window, points are added into the bucket of their digit and the buckets are summed with their weights.
+/// Digits are signed, so a negative digit subtracts the point from a bucket and only 2^(c-1) buckets are needed.
+pub fn pippenger<G: Group>(terms: &[(G::Scalar, G)]) -> G {
+    let c = pippenger_window(terms.len());
+    // One more window than the scalar needs takes the carry out of the top digit
+    let windows = G::Scalar::NUM_BITS as usize / c + 1;
+    let digits: Vec<Vec<i64>> = terms
+        .iter()
+        .map(|(scalar, _)| signed_digits(&limbs(scalar), c, windows))
+        .collect();
+
+    let mut acc = G::identity();
+    for window in (0..windows).rev() {
+        for _ in 0..c {
+            acc = acc.double();
+        }
+        let mut buckets = vec![G::identity(); 1 << (c - 1)];
+        for (digits, (_, point)) in digits.iter().zip(terms) {
+            let digit = digits[window];
+            if digit > 0 {
+                buckets[digit as usize - 1] += point;
+            } else if digit < 0 {
+                buckets[(-digit) as usize - 1] -= point;
+            }
+        }
+        // sum(d * bucket_d) = sum over d of the running sum of the buckets from the top down to d
+        let mut running = G::identity();
+        let mut sum = G::identity();
+        for bucket in buckets.iter().rev() {
+            running += bucket;
+            sum += running;
+        }
+        acc += sum;
+    }
+    acc
+}
+
+/// Bits per Pippenger window for n terms: wider windows mean fewer doublings and passes but more buckets to sum
+fn pippenger_window(n: usize) -> usize {
+    match n {
+        0..=255 => 6,
+        256..=1023 => 8,
+        1024..=4095 => 10,
+        _ => 12,
+    }
+}
+
+/// Split a scalar into `windows` signed c-bit digits in [-2^(c-1), 2^(c-1)), least significant first
+fn signed_digits(limbs: &[u64], c: usize, windows: usize) -> Vec<i64> {
+    let (modulus, half) = (1i64 << c, 1i64 << (c - 1));
+    let mut carry = 0;
+    (0..windows)
+        .map(|window| {
+            let digit = bits(limbs, window * c, c) as i64 + carry;
+            carry = (digit >= half) as i64;
+            digit - carry * modulus
+        })
+        .collect()
+}
+
+/// Little-endian 64-bit limbs of a scalar, with two zero limbs on top so that windows can read past the highest bit
+fn limbs<F: PrimeField>(scalar: &F) -> Vec<u64> {
+    let mut limbs: Vec<u64> = scalar_to_bytes(scalar)
+        .rchunks(8)
+        .map(|chunk| chunk.iter().fold(0, |acc, byte| (acc << 8) | *byte as u64))
+        .collect();
+    limbs.extend([0, 0]);
+    limbs
+}
+
+/// Read `count` (at most 63) bits of little-endian limbs starting at bit `pos`
+fn bits(limbs: &[u64], pos: usize, count: usize) -> u64 {
+    let (limb, shift) = (pos / 64, pos % 64);
+    let mut window = limbs[limb] >> shift;
+    if shift + count > 64 {
+        window |= limbs[limb + 1] << (64 - shift);
+    }
+    window & ((1 << count) - 1)
+}
+
+/// Width-w non-adjacent form: digits are zero or odd with absolute value below 2^(w-1), and any w consecutive digits have at most one nonzero
+fn wnaf(limbs: &[u64], width: usize) -> Vec<i64> {
+    // The two padding limbs leave room for the final carry
+    let len = (limbs.len() - 2) * 64 + 1;
+    let mut naf = vec![0i64; len];
+    let (modulus, half) = (1u64 << width, 1u64 << (width - 1));
+    let mut carry = 0;
+    let mut pos = 0;
+    while pos < len {
+        let window = carry + bits(limbs, pos, width);
+        if window & 1 == 0 {
+            pos += 1;
+            continue;
+        }
+        if window < half {
+            carry = 0;
+            naf[pos] = window as i64;
+        } else {
+            carry = 1;
+            naf[pos] = window as i64 - modulus as i64;
+        }
+        pos += width;
+    }
+    naf
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use elliptic_curve::Field;
+
+    /// Sum of separate scalar multiplications to compare against
+    fn naive<G: Group>(terms: &[(G::Scalar, G)]) -> G {
+        terms
+            .iter()
+            .fold(G::identity(), |acc, (scalar, point)| acc + *point * scalar)
+    }
+
+    fn random_terms<G: Group>(n: usize) -> Vec<(G::Scalar, G)> {
+        let mut rng = rand::thread_rng();
+        (0..n)
+            .map(|_| (G::Scalar::random(&mut rng), G::random(&mut rng)))
+            .collect()
+    }
+
+    fn check<G: Group>(terms: &[(G::Scalar, G)]) {
+        let expected = naive(terms);
+        assert_eq!(straus(terms), expected);
+        assert_eq!(pippenger(terms), expected);
+        assert_eq!(msm(terms), expected);
+    }
+
+    #[test]
+    fn msm_test_random() {
+        for n in [0, 1, 2, 3, 10, 65, 300] {
+            check(&random_terms::<k256::ProjectivePoint>(n));
+            check(&random_terms::<p256::ProjectivePoint>(n));
+        }
+        check(&random_terms::<p384::ProjectivePoint>(20));
+    }
+
+    // scalars with long runs of ones and zeros exercise the carries in the wNAF and the top windows
+    #[test]
+    fn msm_test_edge_scalars() {
+        use k256::{ProjectivePoint, Scalar};
+        let scalars = [
+            Scalar::ZERO,
+            Scalar::ONE,
+            -Scalar::ONE,
+            Scalar::from(u64::MAX),
+            -Scalar::from(u64::MAX),
+            Scalar::from(1u64 << 63),
+            Scalar::TWO_INV,
+        ];
+        let terms: Vec<_> = scalars
+            .iter()
+            .map(|scalar| (*scalar, ProjectivePoint::random(rand::thread_rng())))
+            .collect();
+        check(&terms);
+        check(&[(-Scalar::ONE, ProjectivePoint::IDENTITY)]);
+    }
+
+    #[test]
+    fn wnaf_test() {
+        for _ in 1..100 {
+            let scalar = k256::Scalar::random(rand::thread_rng());
+            let naf = wnaf(&limbs(&scalar), WNAF_WIDTH);
+            let mut value = k256::Scalar::ZERO;
+            for (i, digit) in naf.iter().enumerate().rev() {
+                value = value.double();
+                value += k256::Scalar::from(digit.unsigned_abs())
+                    * if *digit < 0 {
+                        -k256::Scalar::ONE
+                    } else {
+                        k256::Scalar::ONE
+                    };
+                if *digit != 0 {
+                    assert!(digit % 2 != 0 && digit.abs() < 1 << (WNAF_WIDTH - 1));
+                    assert!(naf[i + 1..].iter().take(WNAF_WIDTH - 1).all(|d| *d == 0));
+                }
+            }
+            assert_eq!(value, scalar);
+        }
+    }
+}
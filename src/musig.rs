@@ -1,310 +1,1076 @@
-use elliptic_curve::{generic_array::GenericArray, group::GroupEncoding, Field, Group};
-use sha2::digest::generic_array::typenum::U32;
-use sha2::{Digest, Sha256};
-// Signer
-// MuSig
-// Signature
+use elliptic_curve::{group::GroupEncoding, Field, Group};
+use rand::{CryptoRng, RngCore};
+use sha2::{digest::Output, Digest, Sha256};
+use std::{fmt, marker::PhantomData};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
 
-// round_1 -> (a_map, x)
-// round_2 -> bool
-// round_3 -> Signature
+pub use crate::bip340::Signature;
+use crate::{
+    all_received,
+    bip340::{with_even_y, XOnlyPublicKey},
+    ecdsa::CurveGroup,
+    hash_to_scalar,
+    keys::SecretScalar,
+    msm::msm,
+    point_from_bytes, receive, scalar_from_bytes, scalar_to_bytes,
+    schnorr::{verify_equations, Equation},
+    tagged_hash, Error, KeyPair, PublicKey, SecretKey,
+};
+
+// MuSig2, the two-round version of MuSig described in BIP-327
+// https://github.com/bitcoin/bips/blob/master/bip-0327.mediawiki
+// Each signer has two nonces r_1, r_2. Their points R_1 = r_1 * G and R_2 = r_2 * G do not depend on the message, so they can be
+// sent ahead of time and the only round that needs the message is the one exchanging partial signatures.
+//
+// `SignerSession` and `Coordinator` run the protocol between separate parties. `MuSig` runs every round locally for a set of
+// `Signer`s, which is useful for testing. Signatures verify with `verify` against the signers' `KeyAggContext`, or with
+// `verify_aggregated` against its aggregate key alone.
+//
+// Key aggregation, the nonce coefficient, signing and partial signature verification follow BIP-327 with the digest D in its
+// tagged hashes. With SHA-256 over secp256k1 the signatures are BIP-340 signatures for the x-only aggregate key, which
+// `bip340::verify` accepts, and pass the BIP-327 test vectors.
+//
+// Signing twice with the same nonces for different challenges reveals the secret key, so secret nonces live in a `SecretNonce`
+// that cannot be copied and is used up by signing. A `SessionId` shared by all parties ties round messages to one session.
 
 // Type state encode state of signature scheme; it is impossible to complete a signature round without all previous required rounds being completed.
 
 /// Initial signature state
-struct R0<'a, G: Group + GroupEncoding>(&'a mut MuSig<'a, G>);
+struct R0<'a, G: CurveGroup, D: Digest>(&'a mut MuSig<'a, G, D>);
 /// State after completing round 1 of the signature process
-struct R1<'a, G: Group + GroupEncoding>(&'a mut MuSig<'a, G>);
+struct R1<'a, G: CurveGroup, D: Digest>(&'a mut MuSig<'a, G, D>);
 /// State after completing round 2 of the signature process
-struct R2<'a, G: Group + GroupEncoding>(&'a mut MuSig<'a, G>);
+struct R2<'a, G: CurveGroup, D: Digest>(&'a mut MuSig<'a, G, D>);
 /// State after completing round 3 of the signature process
-struct R3<'a, G: Group + GroupEncoding>(&'a mut MuSig<'a, G>);
-
+struct R3<'a, G: CurveGroup, D: Digest>(&'a mut MuSig<'a, G, D>);
 
-
-impl<'a, G: Group + GroupEncoding> From<&'a mut MuSig<'a, G>> for R0<'a, G> {
+impl<'a, G: CurveGroup, D: Digest> From<&'a mut MuSig<'a, G, D>> for R0<'a, G, D> {
     /// Create R0 state from initial multi sig
-    fn from(m: &'a mut MuSig<'a, G>) -> Self {
+    fn from(m: &'a mut MuSig<'a, G, D>) -> Self {
         Self(m)
     }
 }
 
-impl<'a, G: Group + GroupEncoding> MuSig<'a, G> {
+impl<'a, G: CurveGroup, D: Digest> MuSig<'a, G, D> {
     /// Complete all stages of signing from intial setup
-    fn sign(&'a mut self) -> Signature<G> {
-        R0::from(self).sign().clone()
+    pub fn sign(&'a mut self) -> Result<Signature<G>, Error> {
+        R0::from(self).sign().cloned()
     }
 }
 
-impl<'a, G: Group + GroupEncoding> R0<'a, G> {
+impl<'a, G: CurveGroup, D: Digest> R0<'a, G, D> {
     /// Round 1: Aggregate public keys, create 'a' for each signer by hashing all public keys plus each signer's individual public key and create aggregated public key
-    fn round_1(self) -> R1<'a, G> {
+    fn round_1(self) -> Result<R1<'a, G, D>, Error> {
         let m: &mut _ = self.0;
 
-        let all_pk = m.signers.iter().map(Signer::pk).collect::<Vec<_>>();
-        // TODO: don't actually clone `all_pk` repeatedly.
-        // hash it once, then incrementally hash the unique suffix.
-
-        m.a_vec = m
-            .signers
+        let all_pk = m.signers.iter().map(Signer::public_key).collect::<Vec<_>>();
+        let key_agg = match &m.key_agg {
+            Some(key_agg) => key_agg.clone(),
+            None => KeyAggContext::<G, D>::new_with_options(&all_pk, m.options)?,
+        };
+        // Sorting may put the keys in a different order than the signers, so look each signer up by key
+        m.a_vec = all_pk
             .iter()
-            .map(|signer| Some(hash_agg(all_pk.clone(), signer.pk())))
-            .collect();
+            .map(|pk| {
+                key_agg
+                    .index_of(*pk)
+                    .map(|i| Some(key_agg.signing_coefficient(i)))
+                    .ok_or(Error::UnknownSigner)
+            })
+            .collect::<Result<_, _>>()?;
+        m.x = key_agg.aggregate_key().point();
+        m.key_agg = Some(key_agg);
 
-        //hash_agg(all_pk.clone(), signer.pk())
-        m.x = m
-            .signers
-            .iter()
-            .zip(m.a_vec.iter())
-            .map(|(signer, a)| signer.pk() * a.expect("missing a"))
-            .sum();
-
-        R1(m)
+        Ok(R1(m))
     }
 
     /// Run all rounds of signing and create a signature given an initial multi sig.
-    fn sign(self) -> &'a Signature<G> {
-        self.round_1()
-            .round_2()
-            .round_3()
+    fn sign(self) -> Result<&'a Signature<G>, Error> {
+        Ok(self
+            .round_1()?
+            .round_2()?
+            .round_3()?
             .0
             .signature
             .as_ref()
-            .expect("missing signature")
+            .expect("missing signature"))
     }
 }
 
-impl<'a, G: Group + GroupEncoding> R1<'a, G> {
-    /// Round 2: Each signer randomly generates r, a scalar, and creates R = r * generator. They compute hash_com(R) and publish this commitment. Then, all signers publicize their R and verify all commitments.
-    fn round_2(self) -> R2<'a, G> {
+impl<'a, G: CurveGroup, D: Digest> R1<'a, G, D> {
+    /// Round 2: Each signer publishes its preprocessed nonce points (R_1, R_2), and they are summed into the aggregate nonce (R_1, R_2).
+    /// Nothing here depends on the message, so this round can run before the message is known.
+    /// Fails with `Error::NonceReused` if a signer's nonces were already used up by another session.
+    fn round_2(self) -> Result<R2<'a, G, D>, Error> {
         let m: &mut _ = self.0;
 
-        m.commitment_vec = m.signers.iter().map(|signer| signer.commit()).collect();
-        m.opened_commitment_vec = m.signers.iter().map(|signer| signer.r_point()).collect();
-        let verifier: bool = m
+        m.nonce_points_vec = m
             .signers
             .iter()
-            .map(|signer| signer.verify_all_commits(&m.opened_commitment_vec, &m.commitment_vec))
-            .fold(true, |acc, ver| acc && ver);
+            .map(Signer::nonce_points)
+            .collect::<Result<_, _>>()?;
+        m.aggregate_nonce = aggregate_nonce(&m.nonce_points_vec);
 
-        // TODO: Add an error type so that this can fail if `verifier == false`
-        if verifier == true {
-            return R2(m);
-        } else {
-            panic!()
-        }
+        Ok(R2(m))
     }
 }
 
-impl<'a, G: Group + GroupEncoding> R2<'a, G> {
-    /// Round 3: Create collective R and create signature
-    fn round_3(self) -> R3<'a, G> {
+impl<'a, G: CurveGroup, D: Digest> R2<'a, G, D> {
+    /// Round 3: Bind the aggregate nonce to the message with b, compute R = R_1 + b * R_2, and sum the signers' partial signatures.
+    /// Signers negate their nonces if R has an odd y, so that the signature's R is the point with even y for its x coordinate.
+    /// Every partial signature is checked against its signer's published nonce points and public key, and
+    /// `Error::InvalidPartialSignatures` names the signers whose partial signature is wrong.
+    /// The signers' nonces are used up, so they must be refreshed before signing again.
+    fn round_3(self) -> Result<R3<'a, G, D>, Error> {
         let m: &mut _ = self.0;
 
-        let r_point = m
-            .signers
-            .iter()
-            .fold(G::identity(), |acc, signer| acc + signer.r_point());
-
-        let c = hash_sig(m.x, r_point, m.message);
+        let values = challenge::<G, D>(m.x, m.aggregate_nonce, m.message);
 
-        let s = m
+        let a_vec: Vec<G::Scalar> = m.a_vec.iter().map(|a| a.expect("a missing")).collect();
+        let partial_signatures: Vec<G::Scalar> = m
             .signers
-            .iter()
-            .enumerate()
-            .fold(G::Scalar::ZERO, |acc, (i, signer)| {
-                let a = m.a_vec[i].expect("a missing");
-                acc + signer.s(c, a)
-            });
+            .iter_mut()
+            .zip(&a_vec)
+            .map(|(signer, a)| signer.s(values, *a))
+            .collect::<Result<_, _>>()?;
+        let pk_list: Vec<G> = m.signers.iter().map(Signer::pk).collect();
+        verify_partial_signatures(
+            &partial_signatures,
+            &m.nonce_points_vec,
+            &pk_list,
+            &a_vec,
+            values,
+        )?;
 
-        m.signature = Some(Signature { s, r_point });
+        let key_agg = m.key_agg.as_ref().expect("missing key_agg");
+        m.signature = Some(key_agg.aggregate_signature(partial_signatures, values));
 
-        R3(m)
+        Ok(R3(m))
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-/// Represents a signer with a secret key, public key, and randomly generated value r
-struct Signer<G: Group> {
-    sk: G::Scalar,
-    pk: G,
-    r: G::Scalar,
+/// Represents a signer with a key pair and the secret nonces r_1 and r_2 for its next signature.
+/// D is the hash function the nonces are derived with, the same as for the `MuSig` it signs in, SHA-256 unless another is given.
+#[derive(Debug)]
+pub struct Signer<G: Group, D: Digest = Sha256> {
+    key_pair: KeyPair<G>,
+    nonce: Option<SecretNonce<G>>,
+    digest: PhantomData<D>,
 }
 
-impl<G: Group + GroupEncoding> Signer<G> {
-    /// creates the point R = generator * r
-    fn r_point(&self) -> G {
-        G::generator() * self.r
+impl<G: CurveGroup, D: Digest> Signer<G, D> {
+    /// Create a signer with a random key pair and nonces
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Create a signer with a random key pair and nonces, drawing randomness from the given RNG
+    pub fn random_with_rng(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self::preprocess_with_rng(KeyPair::random_with_rng(rng), rng)
+    }
+
+    /// Create a signer for a key pair with fresh nonces, before any message is known.
+    /// The aggregate key is not known yet either, so the nonces are bound only to the secret key; `refresh_nonce` binds later ones to the session.
+    pub fn preprocess(key_pair: KeyPair<G>) -> Self {
+        Self::preprocess_with_rng(key_pair, &mut rand::thread_rng())
+    }
+
+    /// Like `preprocess`, drawing the nonces from the given RNG
+    pub fn preprocess_with_rng(key_pair: KeyPair<G>, rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        let mut signer = Signer {
+            key_pair,
+            nonce: None,
+            digest: PhantomData,
+        };
+        signer.nonce = Some(SecretNonce::generate::<D>(
+            signer.key_pair.secret_key(),
+            None,
+            None,
+            None,
+            rng,
+        ));
+        signer
+    }
+
+    /// Replace the signer's nonces with fresh ones for the next session, e.g. after they were used up by a signature.
+    /// The nonces are bound to the context's aggregate key and the session ID.
+    pub fn refresh_nonce(&mut self, key_agg: &KeyAggContext<G, D>, session_id: SessionId) {
+        self.refresh_nonce_with_rng(key_agg, session_id, &mut rand::thread_rng())
     }
 
-    /// Creates the signer's contribution to the collective signature: r + c * a * sk
-    fn s(&self, c: G::Scalar, a: G::Scalar) -> G::Scalar {
-        self.r + c * a * self.sk
+    /// Like `refresh_nonce`, drawing the nonces from the given RNG
+    pub fn refresh_nonce_with_rng(
+        &mut self,
+        key_agg: &KeyAggContext<G, D>,
+        session_id: SessionId,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) {
+        self.nonce = Some(SecretNonce::generate::<D>(
+            self.key_pair.secret_key(),
+            Some(key_agg.aggregate_key()),
+            Some(&session_id),
+            None,
+            rng,
+        ));
     }
 
     /// Returns the signer's public key
-    fn pk(&self) -> G {
-        self.pk
+    pub fn public_key(&self) -> PublicKey<G> {
+        self.key_pair.public_key()
     }
 
-    /// Returns hash_com of the signer's R point
-    fn commit(&self) -> G::Scalar {
-        hash_com(self.r_point())
+    /// Returns the nonce points R_1 = generator * r_1 and R_2 = generator * r_2, or `Error::NonceReused` if the nonces were used up
+    pub fn nonce_points(&self) -> Result<(G, G), Error> {
+        self.nonce
+            .as_ref()
+            .map(SecretNonce::nonce_points)
+            .ok_or(Error::NonceReused)
     }
 
-    /// Verify a commitment to a randomly generated point like one produced by the commit function
-    fn verify_commit(commitment: G::Scalar, r_point: G) -> bool {
-        hash_com(r_point) == commitment
+    /// Creates the signer's contribution to the collective signature: r_1 + b * r_2 + e * a * sk, using up its nonces
+    fn s(&mut self, values: (G::Scalar, G, G::Scalar), a: G::Scalar) -> Result<G::Scalar, Error> {
+        let nonce = self.nonce.take().ok_or(Error::NonceReused)?;
+        Ok(nonce.sign(self.key_pair.secret_key(), values, a))
     }
 
-    /// Verify commitmentss from all other signers
-    fn verify_all_commits(&self, r_point_vec: &Vec<G>, commit_vec: &Vec<G::Scalar>) -> bool {
-        let verifier = commit_vec
-            .iter()
-            .zip(r_point_vec)
-            .fold(true, |acc, (commitment, r_point)| {
-                acc && Signer::<G>::verify_commit(*commitment, *r_point)
-            });
-        verifier
+    /// Returns the signer's public key
+    fn pk(&self) -> G {
+        self.key_pair.public_key().point()
     }
 }
 
-#[derive(Debug, Clone)]
-
-/// Represents a signature process with signers, a message, a vector of 'a' values, commitments, opened commitments, a collective public key, and a signature.
-struct MuSig<'a, G: Group> {
-    signers: &'a [Signer<G>],
+/// Represents a signature process with signers, a message, a vector of 'a' values, the signers' nonce points and their aggregate,
+/// a collective public key, and a signature.
+/// D is the hash function used for aggregation, the nonce coefficient b and the challenge, SHA-256 unless another is given.
+/// Signing uses up the signers' nonces.
+#[derive(Debug)]
+pub struct MuSig<'a, G: CurveGroup, D: Digest = Sha256> {
+    signers: &'a mut [Signer<G, D>],
     message: &'a [u8],
+    options: KeyAggOptions,
+    key_agg: Option<KeyAggContext<G, D>>,
     a_vec: Vec<Option<G::Scalar>>,
-    commitment_vec: Vec<G::Scalar>,
-    opened_commitment_vec: Vec<G>,
+    nonce_points_vec: Vec<(G, G)>,
+    aggregate_nonce: (G, G),
 
     /// Collective public key
     x: G,
     signature: Option<Signature<G>>,
+    digest: PhantomData<D>,
 }
 
-impl<'a, G: Group> MuSig<'a, G> {
+impl<'a, G: CurveGroup, D: Digest> MuSig<'a, G, D> {
     /// Create new signature process with signers and a message and no data in the other fields
-    fn new(signers: &'a [Signer<G>], message: &'a [u8]) -> Self {
+    pub fn new(signers: &'a mut [Signer<G, D>], message: &'a [u8]) -> Self {
+        Self::new_with_options(signers, message, KeyAggOptions::default())
+    }
+
+    /// Like `new`, aggregating the signers' keys with the given options
+    pub fn new_with_options(
+        signers: &'a mut [Signer<G, D>],
+        message: &'a [u8],
+        options: KeyAggOptions,
+    ) -> Self {
+        let a_vec = vec![None; signers.len()];
         MuSig {
-            signers: signers,
-            message: message,
-            a_vec: vec![None; signers.len()],
-            commitment_vec: Vec::new(),
-            opened_commitment_vec: Vec::new(),
+            signers,
+            message,
+            options,
+            key_agg: None,
+            a_vec,
+            nonce_points_vec: Vec::new(),
+            aggregate_nonce: (G::identity(), G::identity()),
             x: G::identity(),
             signature: None,
+            digest: PhantomData,
+        }
+    }
+
+    /// Like `new`, using an existing aggregation context, e.g. one with tweaks applied, instead of aggregating the signers' keys.
+    /// Every signer's key must be in the context.
+    pub fn new_with_context(
+        signers: &'a mut [Signer<G, D>],
+        message: &'a [u8],
+        key_agg: &KeyAggContext<G, D>,
+    ) -> Self {
+        let mut musig = Self::new(signers, message);
+        musig.key_agg = Some(key_agg.clone());
+        musig
+    }
+}
+
+/// A signer's secret nonces (r_1, r_2) for one signature. It cannot be cloned, signing consumes it, and it is zeroized when dropped.
+struct SecretNonce<G: Group> {
+    r_1: SecretScalar<G::Scalar>,
+    r_2: SecretScalar<G::Scalar>,
+}
+
+impl<G: CurveGroup> SecretNonce<G> {
+    /// Derive the nonces as in NonceGen of BIP-327, by hashing fresh randomness with the secret key and whichever of the aggregate key,
+    /// session ID and message are known. The randomness is required: nonces that depend only on the inputs would repeat when the same
+    /// message is signed again while the other signers choose new nonces, which gives a second equation for the secret key.
+    /// The other inputs keep the nonces apart across keys and sessions even if the RNG is weak.
+    fn generate<D: Digest>(
+        secret_key: &SecretKey<G>,
+        aggregate_key: Option<PublicKey<G>>,
+        session_id: Option<&SessionId>,
+        message: Option<&[u8]>,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Self {
+        let mut rand = [0u8; 32];
+        rng.fill_bytes(&mut rand);
+        let sk = Zeroizing::new(scalar_to_bytes(secret_key.scalar()));
+        let aggregate_key = aggregate_key.map(|x| x.point().x());
+        let inputs = [
+            Some(&rand[..]),
+            Some(&sk[..]),
+            aggregate_key.as_deref(),
+            session_id.map(|id| &id.0[..]),
+            message,
+        ];
+        // Each input is length prefixed, with a marker for a missing one, so that different inputs never hash the same bytes
+        let mut encoded = Vec::new();
+        for input in inputs {
+            match input {
+                Some(bytes) => {
+                    encoded.push(1);
+                    encoded.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+                    encoded.extend_from_slice(bytes);
+                }
+                None => encoded.push(0),
+            }
+        }
+        let nonce = |i: u8| hash_to_scalar(&tagged_hash::<D>("MuSig/nonce", &[&encoded, &[i]]));
+        SecretNonce {
+            r_1: SecretScalar(nonce(1)),
+            r_2: SecretScalar(nonce(2)),
+        }
+    }
+
+    /// Returns the nonce points (R_1, R_2) = (r_1 * G, r_2 * G)
+    fn nonce_points(&self) -> (G, G) {
+        (G::generator() * self.r_1.0, G::generator() * self.r_2.0)
+    }
+
+    /// Compute the partial signature r_1 + b * r_2 + e * a * sk for the session values (b, R, e) and the key coefficient a,
+    /// consuming the nonces. The nonces are negated if R has an odd y.
+    fn sign(
+        self,
+        secret_key: &SecretKey<G>,
+        (b, r_point, e): (G::Scalar, G, G::Scalar),
+        a: G::Scalar,
+    ) -> G::Scalar {
+        let k = self.r_1.0 + b * self.r_2.0;
+        let k = if r_point.y_is_odd() { -k } else { k };
+        k + e * a * secret_key.scalar()
+    }
+}
+
+impl<G: Group> fmt::Debug for SecretNonce<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretNonce(<redacted>)")
+    }
+}
+
+impl<G: Group> ZeroizeOnDrop for SecretNonce<G> {}
+
+/// Identifies one distributed signing session. All parties must use the same ID, and a fresh random one for every session,
+/// so that round messages left over from another session are rejected with `Error::SessionMismatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionId([u8; 32]);
+
+impl SessionId {
+    /// Generate a random session ID
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Generate a random session ID, drawing randomness from the given RNG
+    pub fn random_with_rng(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        SessionId(bytes)
+    }
+
+    /// Returns the 32 bytes of the ID
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Fail with `Error::SessionMismatch` unless a message carries this ID
+    fn check(&self, other: &SessionId) -> Result<(), Error> {
+        if self != other {
+            return Err(Error::SessionMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl From<[u8; 32]> for SessionId {
+    fn from(bytes: [u8; 32]) -> Self {
+        SessionId(bytes)
+    }
+}
+
+// Distributed signing: each party runs its own `SignerSession`, which holds only that party's key pair and nonces, and exchanges
+// a `NonceMessage` and then a `PartialSignatureMessage` with the others. A `Coordinator` holding no secrets collects the messages
+// and assembles the `Signature`. Signers are identified by their index in the list of public keys, which all parties must use in the same order.
+
+/// The public state of a distributed session: its ID, the key aggregation context and the nonce points received so far
+#[derive(Debug, Clone)]
+struct Session<G: Group, D: Digest> {
+    id: SessionId,
+    key_agg: KeyAggContext<G, D>,
+    nonce_points_vec: Vec<Option<(G, G)>>,
+}
+
+impl<G: CurveGroup, D: Digest> Session<G, D> {
+    /// Start a session for the signers of a key aggregation context, with no nonce points received yet
+    fn new(key_agg: &KeyAggContext<G, D>, id: SessionId) -> Self {
+        Session {
+            id,
+            key_agg: key_agg.clone(),
+            nonce_points_vec: vec![None; key_agg.public_keys().len()],
+        }
+    }
+
+    /// Record a signer's nonce points, failing with `Error::SessionMismatch` if the message is from another session
+    fn receive_nonce(&mut self, message: &NonceMessage<G>) -> Result<(), Error> {
+        self.id.check(&message.session_id)?;
+        receive(
+            &mut self.nonce_points_vec,
+            message.index,
+            message.nonce_points,
+        )
+    }
+
+    /// Compute b, R and the challenge e for the message, failing with `Error::MissingMessages` until every signer's nonce points are in
+    fn challenge(&self, message: &[u8]) -> Result<(G::Scalar, G, G::Scalar), Error> {
+        let nonce_points_vec: Vec<(G, G)> = all_received(&self.nonce_points_vec)?;
+        Ok(challenge::<G, D>(
+            self.key_agg.aggregate_key().point(),
+            aggregate_nonce(&nonce_points_vec),
+            message,
+        ))
+    }
+}
+
+/// One signer's side of a distributed session. It holds the signer's own key pair and nonces, and nothing secret of anyone else.
+/// It cannot be cloned, and signing consumes it, so its nonces sign at most once.
+#[derive(Debug)]
+pub struct SignerSession<G: Group, D: Digest = Sha256> {
+    session: Session<G, D>,
+    key_pair: KeyPair<G>,
+    index: usize,
+    nonce: SecretNonce<G>,
+    /// The message the nonces were bound to, if it was known when the session started
+    message: Option<Vec<u8>>,
+}
+
+impl<G: CurveGroup, D: Digest> SignerSession<G, D> {
+    /// Join the session with the given ID as the owner of a key pair, given the aggregation context of all signers' keys and the signer's index
+    /// in its list of public keys, and generate fresh nonces. The index tells apart signers that share a key; `KeyAggContext::index_of` finds
+    /// it for a key that occurs once. Fails with `Error::UnknownSigner` unless the context holds the signer's key at that index.
+    /// The nonces are bound to the key, the aggregate key, the session ID and the message if it is already known, in which case `sign` only signs that message.
+    /// Returns the session and the nonce message for the other signers and the coordinator, which can be sent before the message to sign is known.
+    pub fn new(
+        key_pair: KeyPair<G>,
+        key_agg: &KeyAggContext<G, D>,
+        signer_index: usize,
+        session_id: SessionId,
+        message: Option<&[u8]>,
+    ) -> Result<(Self, NonceMessage<G>), Error> {
+        Self::new_with_rng(
+            key_pair,
+            key_agg,
+            signer_index,
+            session_id,
+            message,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Like `new`, drawing the randomness for the nonces from the given RNG
+    pub fn new_with_rng(
+        key_pair: KeyPair<G>,
+        key_agg: &KeyAggContext<G, D>,
+        signer_index: usize,
+        session_id: SessionId,
+        message: Option<&[u8]>,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self, NonceMessage<G>), Error> {
+        if key_agg.public_keys().get(signer_index) != Some(&key_pair.public_key()) {
+            return Err(Error::UnknownSigner);
         }
+        let mut session = Session::new(key_agg, session_id);
+        let nonce = SecretNonce::generate::<D>(
+            key_pair.secret_key(),
+            Some(key_agg.aggregate_key()),
+            Some(&session_id),
+            message,
+            rng,
+        );
+        let nonce_message = NonceMessage {
+            session_id,
+            index: signer_index,
+            nonce_points: nonce.nonce_points(),
+        };
+        session.receive_nonce(&nonce_message)?;
+        let signer_session = SignerSession {
+            session,
+            key_pair,
+            index: signer_index,
+            nonce,
+            message: message.map(<[u8]>::to_vec),
+        };
+        Ok((signer_session, nonce_message))
+    }
+
+    /// Returns this signer's index in the list of public keys
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Take in another signer's nonce message
+    pub fn receive_nonce(&mut self, message: &NonceMessage<G>) -> Result<(), Error> {
+        self.session.receive_nonce(message)
+    }
+
+    /// Create this signer's partial signature s_i = r_1 + b * r_2 + e * a_i * g * g_acc * sk once every nonce message has been received,
+    /// where g is -1 if the aggregate key has an odd y and 1 otherwise, and the nonces are negated if R has an odd y.
+    /// Fails with `Error::SessionMismatch` if the session was started with a different message.
+    /// The session is consumed, even on failure, so that its nonces cannot sign a second message.
+    pub fn sign(self, message: &[u8]) -> Result<PartialSignatureMessage<G>, Error> {
+        if self
+            .message
+            .as_deref()
+            .is_some_and(|bound| bound != message)
+        {
+            return Err(Error::SessionMismatch);
+        }
+        let values = self.session.challenge(message)?;
+        let a = self.session.key_agg.signing_coefficient(self.index);
+        let s = self.nonce.sign(self.key_pair.secret_key(), values, a);
+        Ok(PartialSignatureMessage {
+            session_id: self.session.id,
+            index: self.index,
+            s,
+        })
     }
 }
 
+/// Collects the round messages of all signers and assembles the signature. The coordinator holds no secrets and may be one of the signers or a separate party.
 #[derive(Debug, Clone)]
+pub struct Coordinator<G: Group, D: Digest = Sha256> {
+    session: Session<G, D>,
+    message: Vec<u8>,
+    partial_signatures: Vec<Option<G::Scalar>>,
+}
+
+impl<G: CurveGroup, D: Digest> Coordinator<G, D> {
+    /// Coordinate the session with the given ID, signing the message under the aggregation context of all signers' keys
+    pub fn new(key_agg: &KeyAggContext<G, D>, session_id: SessionId, message: &[u8]) -> Self {
+        Coordinator {
+            session: Session::new(key_agg, session_id),
+            message: message.to_vec(),
+            partial_signatures: vec![None; key_agg.public_keys().len()],
+        }
+    }
+
+    /// Take in a signer's nonce message
+    pub fn receive_nonce(&mut self, message: &NonceMessage<G>) -> Result<(), Error> {
+        self.session.receive_nonce(message)
+    }
+
+    /// Take in a signer's partial signature, failing with `Error::SessionMismatch` if it is from another session
+    pub fn receive_partial_signature(
+        &mut self,
+        message: &PartialSignatureMessage<G>,
+    ) -> Result<(), Error> {
+        self.session.id.check(&message.session_id)?;
+        receive(&mut self.partial_signatures, message.index, message.s)
+    }
 
-/// Represents a signature with s and R
-struct Signature<G: Group> {
+    /// Assemble the BIP-340 signature (R, s) with s the sum of the partial signatures plus e * g * t_acc, failing with
+    /// `Error::MissingMessages` until all of them are in.
+    /// Each partial signature is checked first, and `Error::InvalidPartialSignatures` names the signers that sent a wrong one.
+    pub fn signature(&self) -> Result<Signature<G>, Error> {
+        let key_agg = &self.session.key_agg;
+        let values = self.session.challenge(&self.message)?;
+        let partial_signatures = all_received(&self.partial_signatures)?;
+        let coefficients: Vec<G::Scalar> = (0..partial_signatures.len())
+            .map(|i| key_agg.signing_coefficient(i))
+            .collect();
+        verify_partial_signatures(
+            &partial_signatures,
+            &all_received(&self.session.nonce_points_vec)?,
+            &key_agg.points(),
+            &coefficients,
+            values,
+        )?;
+        Ok(key_agg.aggregate_signature(partial_signatures, values))
+    }
+}
+
+/// Round 1 message: a signer's public nonce points (R_1, R_2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceMessage<G: Group> {
+    session_id: SessionId,
+    index: usize,
+    nonce_points: (G, G),
+}
+
+impl<G: CurveGroup> NonceMessage<G> {
+    /// Returns the ID of the session the message belongs to
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Returns the index of the signer that sent the message
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the nonce points (R_1, R_2)
+    pub fn nonce_points(&self) -> (G, G) {
+        self.nonce_points
+    }
+
+    /// Encode as session ID || index || R_1 || R_2, with the index as 4 big-endian bytes and the points in their `GroupEncoding`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = header_to_bytes(&self.session_id, self.index);
+        bytes.extend_from_slice(self.nonce_points.0.to_bytes().as_ref());
+        bytes.extend_from_slice(self.nonce_points.1.to_bytes().as_ref());
+        bytes
+    }
+
+    /// Decode session ID || index || R_1 || R_2, failing with `Error::InvalidEncoding` if the length is wrong or a point does not decode,
+    /// and with `Error::IdentityPoint` if a nonce point is the identity
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let len = G::Repr::default().as_ref().len();
+        if bytes.len() != HEADER_LEN + 2 * len {
+            return Err(Error::InvalidEncoding);
+        }
+        let (header, points) = bytes.split_at(HEADER_LEN);
+        let (session_id, index) = header_from_bytes(header);
+        let r_point_1: G = point_from_bytes(&points[..len])?;
+        let r_point_2: G = point_from_bytes(&points[len..])?;
+        if r_point_1 == G::identity() || r_point_2 == G::identity() {
+            return Err(Error::IdentityPoint);
+        }
+        Ok(NonceMessage {
+            session_id,
+            index,
+            nonce_points: (r_point_1, r_point_2),
+        })
+    }
+}
+
+/// Round 2 message: a signer's partial signature s_i
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSignatureMessage<G: Group> {
+    session_id: SessionId,
+    index: usize,
     s: G::Scalar,
-    r_point: G,
 }
 
-impl<G: Group> Signature<G> {
+impl<G: Group> PartialSignatureMessage<G> {
+    /// Returns the ID of the session the message belongs to
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Returns the index of the signer that sent the message
+    pub fn index(&self) -> usize {
+        self.index
+    }
 
-    /// Returns the s value of a signature
-    fn s(&self) -> G::Scalar {
+    /// Returns the partial signature s_i
+    pub fn s(&self) -> G::Scalar {
         self.s
     }
 
-    /// Returns the R value of a signature
-    fn r_point(&self) -> G {
-        self.r_point
+    /// Encode as session ID || index || s_i, with the index as 4 big-endian bytes and s_i as a big-endian scalar
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = header_to_bytes(&self.session_id, self.index);
+        bytes.extend_from_slice(&scalar_to_bytes(&self.s));
+        bytes
+    }
+
+    /// Decode session ID || index || s_i, failing with `Error::InvalidEncoding` if the length is wrong or s_i is not less than the group order
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_LEN {
+            return Err(Error::InvalidEncoding);
+        }
+        let (header, s) = bytes.split_at(HEADER_LEN);
+        let (session_id, index) = header_from_bytes(header);
+        Ok(PartialSignatureMessage {
+            session_id,
+            index,
+            s: scalar_from_bytes(s)?,
+        })
     }
 }
 
-/// Verify a signature given the message and a list of public keys used in signing
-fn verify<T: Group + GroupEncoding>(
-    signature: Signature<T>,
-    pk_list: Vec<T>,
-    message: &[u8],
-) -> bool {
-    let s = signature.s();
-    let r_point = signature.r_point();
-    let a_vec: Vec<T::Scalar> = pk_list
-        .clone()
+/// Length of the session ID and signer index that start every round message
+const HEADER_LEN: usize = 32 + 4;
+
+fn header_to_bytes(session_id: &SessionId, index: usize) -> Vec<u8> {
+    let mut bytes = session_id.0.to_vec();
+    bytes.extend_from_slice(&(index as u32).to_be_bytes());
+    bytes
+}
+
+fn header_from_bytes(bytes: &[u8]) -> (SessionId, usize) {
+    let (session_id, index) = bytes.split_at(32);
+    (
+        SessionId(session_id.try_into().expect("session ID must be 32 bytes")),
+        u32::from_be_bytes(index.try_into().expect("index must be 4 bytes")) as usize,
+    )
+}
+
+/// Sum the signers' nonce points into the aggregate nonce (R_1, R_2)
+fn aggregate_nonce<G: Group>(nonce_points_vec: &[(G, G)]) -> (G, G) {
+    nonce_points_vec.iter().fold(
+        (G::identity(), G::identity()),
+        |(acc_1, acc_2), (r_point_1, r_point_2)| (acc_1 + r_point_1, acc_2 + r_point_2),
+    )
+}
+
+/// Check each partial signature s_i * G == R_i,1 + b * R_i,2 + e * a_i * X_i, with the nonce points negated if R has an odd y and
+/// a_i the signer's coefficient including g * g_acc. The checks are batched with `verify_equations`, and on failure the error is
+/// `Error::InvalidPartialSignatures` with the indices of the signers whose partial signature is wrong.
+fn verify_partial_signatures<G: CurveGroup>(
+    partial_signatures: &[G::Scalar],
+    nonce_points_vec: &[(G, G)],
+    pk_list: &[G],
+    a_vec: &[G::Scalar],
+    (b, r_point, e): (G::Scalar, G, G::Scalar),
+) -> Result<(), Error> {
+    let equations: Vec<Equation<G>> = partial_signatures
         .iter()
-        .map(|pk| hash_agg(pk_list.clone(), *pk))
+        .zip(nonce_points_vec)
+        .zip(pk_list.iter().zip(a_vec))
+        .map(|((s, (r_point_1, r_point_2)), (pk, a))| {
+            let nonce = *r_point_1 + *r_point_2 * b;
+            let nonce = if r_point.y_is_odd() { -nonce } else { nonce };
+            Equation::new(*s, nonce, e * a, *pk)
+        })
         .collect();
-    let x = a_vec
-        .iter()
-        .zip(pk_list)
-        .fold(T::identity(), |acc, (a, pk)| acc + pk * a);
-    let c = hash_sig(x, r_point, message);
-    T::generator() * s == r_point + x * c
+    verify_equations(&equations).map_err(|error| match error {
+        Error::InvalidBatch(indices) => Error::InvalidPartialSignatures(indices),
+        error => error,
+    })
 }
 
-// Domain separated hash functions for aggregation, commitment, and signature phases
-
-/// Base hash function that takes in inputs and returns a scalar using Sha256
-fn hash<T: Group>(inputs: Vec<&[u8]>) -> T::Scalar {
-    let mut hasher = Sha256::new();
-    for input in inputs {
-        hasher.update(input)
+/// Bind the aggregate nonce to the aggregate key X and message with b, and return b, R = R_1 + b * R_2 and the challenge e
+fn challenge<G: CurveGroup, D: Digest>(
+    x: G,
+    (r_point_1, r_point_2): (G, G),
+    message: &[u8],
+) -> (G::Scalar, G, G::Scalar) {
+    let b = hash_non::<G, D>(x, r_point_1, r_point_2, message);
+    let mut r_point = msm(&[(G::Scalar::ONE, r_point_1), (b, r_point_2)]);
+    // As in BIP-327, an identity R (which no honest signer can cause) is replaced by the generator rather than aborting
+    if r_point == G::identity() {
+        r_point = G::generator();
     }
-    let hash = hasher.finalize();
-    let mut scalar = <T::Scalar as Field>::ZERO;
-    let scalar256 = <T::Scalar as From<u64>>::from(256);
-    for byte in hash {
-        scalar *= scalar256; // TODO: Maybe do this by doubling?
-        scalar += <T::Scalar as From<u64>>::from(byte as u64)
+    let e = hash_sig::<G, D>(x, r_point, message);
+    (b, r_point, e)
+}
+
+/// Verify a signature against the aggregate key of the signers' `KeyAggContext`, built with the same options as for signing and
+/// including any tweaks applied to it
+pub fn verify<T: CurveGroup, D: Digest>(
+    signature: &Signature<T>,
+    key_agg: &KeyAggContext<T, D>,
+    message: &[u8],
+) -> Result<(), Error> {
+    verify_aggregated::<T, D>(signature, key_agg.aggregate_key(), message)
+}
+
+/// Verify a signature against the aggregate public key X of a `KeyAggContext`, without needing the individual keys.
+/// Only the x coordinate of X is used, as in BIP-340.
+pub fn verify_aggregated<T: CurveGroup, D: Digest>(
+    signature: &Signature<T>,
+    aggregate_key: PublicKey<T>,
+    message: &[u8],
+) -> Result<(), Error> {
+    if !aggregated_equation::<T, D>(signature, aggregate_key, message)?.holds() {
+        return Err(Error::InvalidSignature);
     }
-    scalar
+    Ok(())
+}
+
+/// The equation s * G == R + e * X that `verify` checks, so that MuSig signatures can be batched with `schnorr::verify_equations`
+pub fn equation<T: CurveGroup, D: Digest>(
+    signature: &Signature<T>,
+    key_agg: &KeyAggContext<T, D>,
+    message: &[u8],
+) -> Result<Equation<T>, Error> {
+    aggregated_equation::<T, D>(signature, key_agg.aggregate_key(), message)
+}
+
+/// The equation s * G == R + e * X for the aggregate public key X with even y and the point R with even y and the signature's x coordinate.
+/// Fails with `Error::InvalidSignature` if that x coordinate is not on the curve.
+fn aggregated_equation<T: CurveGroup, D: Digest>(
+    signature: &Signature<T>,
+    aggregate_key: PublicKey<T>,
+    message: &[u8],
+) -> Result<Equation<T>, Error> {
+    let x = with_even_y(aggregate_key.point()).0;
+    let r_point = T::from_x(signature.r(), false).ok_or(Error::InvalidSignature)?;
+    let e = hash_sig::<T, D>(x, r_point, message);
+    Ok(Equation::new(signature.s(), r_point, e, x))
 }
 
+/// Optional settings for MuSig key aggregation. The default is KeyAgg of BIP-327: the keys in the order given, with the second key optimization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyAggOptions {
+    /// Sort the keys with `key_sort` first, so that any order of the same keys gives the same aggregate key
+    pub sort: bool,
+    /// Give the second distinct key in the list the coefficient 1, as BIP-327 requires. Turning it off gives aggregate keys that other BIP-327 implementations do not compute.
+    pub second_key_optimization: bool,
+}
 
-/// Hash agg: Takes in a list of public keys and an individual's public key and hashes them prefixed with "agg"
-fn hash_agg<T: Group + GroupEncoding>(pk_list: Vec<T>, pk: T) -> <T as Group>::Scalar {
-    let mut hash = Sha256::new_with_prefix("agg");
-    let pk_list_bytes: Vec<<T as GroupEncoding>::Repr> =
-        pk_list.iter().map(|pk| pk.to_bytes()).collect();
-    for pk in pk_list_bytes {
-        hash.update(pk);
+impl Default for KeyAggOptions {
+    fn default() -> Self {
+        KeyAggOptions {
+            sort: false,
+            second_key_optimization: true,
+        }
     }
-    hash.update(pk.to_bytes());
-    let hash_scalar = hash_to_scalar::<T>(hash.finalize());
-    return hash_scalar;
 }
 
-/// Creates a commitment by hashing a value prefixed with "com"
-fn hash_com<T: Group + GroupEncoding>(r: T) -> <T as Group>::Scalar {
-    let r_bytes = r.to_bytes();
-    let input = vec!["com".as_bytes(), r_bytes.as_ref()];
-    return hash::<T>(input);
+/// Sort public keys lexicographically by their `GroupEncoding` bytes, like KeySort in BIP-327
+pub fn key_sort<G: CurveGroup>(pk_list: &[PublicKey<G>]) -> Vec<PublicKey<G>> {
+    let mut sorted = pk_list.to_vec();
+    sorted.sort_by_cached_key(|pk| pk.point().to_bytes().as_ref().to_vec());
+    sorted
+}
+
+/// The aggregation of a list of public keys: the aggregate key X = sum(a_i * X_i) and each key's coefficient a_i = hash_agg(L, X_i),
+/// where L is the hash of the whole list, as in KeyAgg of BIP-327. Build it once and pass it to `SignerSession`, `Coordinator` and `verify_aggregated`.
+/// The order of the keys matters and must be the same for every signer and verifier.
+/// Tweaks follow BIP-327: g_acc tracks whether x-only tweaks negated the key and t_acc sums the tweaks, so that X' = g_acc * X + t_acc * G.
+#[derive(Debug)]
+pub struct KeyAggContext<G: Group, D: Digest = Sha256> {
+    pk_list: Vec<PublicKey<G>>,
+    a_vec: Vec<G::Scalar>,
+    /// The aggregate key with all tweaks applied
+    x: PublicKey<G>,
+    gacc: G::Scalar,
+    tacc: G::Scalar,
+    digest: PhantomData<D>,
 }
 
-/// Hashes a collective public key, a collective R point, and a message prefixed with "sig"
-fn hash_sig<T: Group + GroupEncoding>(x: T, r: T, m: &[u8]) -> <T as Group>::Scalar {
-    let x_bytes = x.to_bytes();
-    let r_bytes = r.to_bytes();
-    let input = vec!["sig".as_bytes(), x_bytes.as_ref(), r_bytes.as_ref(), m];
-    return hash::<T>(input);
+impl<G: CurveGroup, D: Digest> KeyAggContext<G, D> {
+    /// Aggregate the public keys in the given order with the default options. Fails with `Error::IdentityPoint` if X is the identity.
+    pub fn new(pk_list: &[PublicKey<G>]) -> Result<Self, Error> {
+        Self::new_with_options(pk_list, KeyAggOptions::default())
+    }
+
+    /// Like `new`, optionally sorting the keys first or not giving the second distinct key the coefficient 1
+    pub fn new_with_options(
+        pk_list: &[PublicKey<G>],
+        options: KeyAggOptions,
+    ) -> Result<Self, Error> {
+        let pk_list = if options.sort {
+            key_sort(pk_list)
+        } else {
+            pk_list.to_vec()
+        };
+        let points: Vec<G> = pk_list.iter().map(PublicKey::point).collect();
+        let l = hash_keys::<G, D>(&points);
+        // The first key in the list that differs from the first key; keys equal to it get a_i = 1, which saves a hash and a scalar multiplication each
+        let second_key = points.iter().find(|pk| **pk != points[0]).copied();
+        let a_vec: Vec<G::Scalar> = points
+            .iter()
+            .map(|pk| {
+                if options.second_key_optimization && Some(*pk) == second_key {
+                    G::Scalar::ONE
+                } else {
+                    hash_agg::<G, D>(&l, *pk)
+                }
+            })
+            .collect();
+        let terms: Vec<_> = a_vec.iter().copied().zip(points).collect();
+        Ok(KeyAggContext {
+            pk_list,
+            a_vec,
+            x: PublicKey::from_point(msm(&terms))?,
+            gacc: G::Scalar::ONE,
+            tacc: G::Scalar::ZERO,
+            digest: PhantomData,
+        })
+    }
+
+    /// Returns the aggregate public key that MuSig signatures for these keys verify against, with any tweaks applied
+    pub fn aggregate_key(&self) -> PublicKey<G> {
+        self.x
+    }
+
+    /// Returns the aggregate public key as the x-only key that `bip340::verify` checks the signatures against
+    pub fn x_only_aggregate_key(&self) -> XOnlyPublicKey<G> {
+        XOnlyPublicKey::from(self.x)
+    }
+
+    /// Returns a context for the plain tweaked key X' = X + t * G. Fails with `Error::IdentityPoint` if X' is the identity.
+    pub fn tweak(&self, tweak: G::Scalar) -> Result<Self, Error> {
+        self.apply_tweak(tweak, false)
+    }
+
+    /// Returns the accumulated tweak t_acc, which the final signature adds e * g * t_acc for
+    pub fn tweak_accumulator(&self) -> G::Scalar {
+        self.tacc
+    }
+
+    /// Returns g_acc, which is -1 if x-only tweaks negated the aggregate key an odd number of times and 1 otherwise
+    pub fn parity_accumulator(&self) -> G::Scalar {
+        self.gacc
+    }
+
+    /// Returns the public keys in the order they were aggregated
+    pub fn public_keys(&self) -> &[PublicKey<G>] {
+        &self.pk_list
+    }
+
+    /// Returns the coefficients a_i, in the same order as the public keys
+    pub fn coefficients(&self) -> &[G::Scalar] {
+        &self.a_vec
+    }
+
+    /// Returns the coefficient a_i of the key at an index, or `None` if the index is past the end of the list
+    pub fn coefficient(&self, index: usize) -> Option<G::Scalar> {
+        self.a_vec.get(index).copied()
+    }
+
+    /// Returns the index of the first occurrence of a public key in the list
+    pub fn index_of(&self, pk: PublicKey<G>) -> Option<usize> {
+        self.pk_list.iter().position(|other| *other == pk)
+    }
+
+    /// Returns the public keys as points
+    fn points(&self) -> Vec<G> {
+        self.pk_list.iter().map(PublicKey::point).collect()
+    }
+
+    /// The scalar signer `index` multiplies its secret key by: a_i * g * g_acc, where g is -1 if X has an odd y and 1 otherwise
+    fn signing_coefficient(&self, index: usize) -> G::Scalar {
+        self.a_vec[index] * self.parity() * self.gacc
+    }
+
+    /// Returns g, which is -1 if the aggregate key X has an odd y and 1 otherwise, so that g * X is the x-only key signatures verify against
+    fn parity(&self) -> G::Scalar {
+        if self.x.point().y_is_odd() {
+            -G::Scalar::ONE
+        } else {
+            G::Scalar::ONE
+        }
+    }
+
+    /// Sum the partial signatures into the signature (R, s), with s = sum(s_i) + e * g * t_acc
+    fn aggregate_signature(
+        &self,
+        partial_signatures: Vec<G::Scalar>,
+        (_, r_point, e): (G::Scalar, G, G::Scalar),
+    ) -> Signature<G> {
+        let s = partial_signatures.into_iter().sum::<G::Scalar>() + e * self.parity() * self.tacc;
+        Signature::from_parts(r_point.x(), s)
+    }
+
+    /// Compute X' = g * X + t * G with g = -1 if `negate` and 1 otherwise, and update g_acc and t_acc to match
+    fn apply_tweak(&self, tweak: G::Scalar, negate: bool) -> Result<Self, Error> {
+        let g = if negate {
+            -G::Scalar::ONE
+        } else {
+            G::Scalar::ONE
+        };
+        let x = msm(&[(g, self.x.point()), (tweak, G::generator())]);
+        Ok(KeyAggContext {
+            pk_list: self.pk_list.clone(),
+            a_vec: self.a_vec.clone(),
+            x: PublicKey::from_point(x)?,
+            gacc: g * self.gacc,
+            tacc: tweak + g * self.tacc,
+            digest: PhantomData,
+        })
+    }
+
+    /// Returns a context for the x-only tweaked key X' = with_even_y(X) + t * G, as used for Taproot outputs whose internal key is X.
+    /// Fails with `Error::IdentityPoint` if X' is the identity.
+    pub fn x_only_tweak(&self, tweak: G::Scalar) -> Result<Self, Error> {
+        self.apply_tweak(tweak, self.x.point().y_is_odd())
+    }
+
+    /// Apply a tweak given as a big-endian scalar, x-only or plain as in ApplyTweak of BIP-327, e.g. a Taproot tweak hash.
+    /// Fails with `Error::InvalidEncoding` if the tweak is not less than the group order, and with `Error::IdentityPoint` if X' is the identity.
+    pub fn tweak_from_bytes(&self, tweak: &[u8], x_only: bool) -> Result<Self, Error> {
+        let tweak = scalar_from_bytes(tweak)?;
+        if x_only {
+            self.x_only_tweak(tweak)
+        } else {
+            self.tweak(tweak)
+        }
+    }
 }
 
-/// Converts the outputs of a hash function (GenericArray of bytes) to a scalar
-fn hash_to_scalar<T: Group>(hash: GenericArray<u8, U32>) -> T::Scalar {
-    let mut scalar = <T::Scalar as Field>::ZERO;
-    let scalar256 = <T::Scalar as From<u64>>::from(256);
-    for byte in hash {
-        scalar *= scalar256; // TODO: Maybe do this by doubling?
-        scalar += <T::Scalar as From<u64>>::from(byte as u64)
+// Implemented by hand so that cloning does not require the digest D to be Clone
+impl<G: Group, D: Digest> Clone for KeyAggContext<G, D> {
+    fn clone(&self) -> Self {
+        KeyAggContext {
+            pk_list: self.pk_list.clone(),
+            a_vec: self.a_vec.clone(),
+            x: self.x,
+            gacc: self.gacc,
+            tacc: self.tacc,
+            digest: PhantomData,
+        }
     }
-    scalar
+}
+
+// The tagged hashes of BIP-327 and BIP-340 for aggregation, the nonce coefficient and the challenge
+
+/// Hashes a list of public keys with the tag "KeyAgg list", giving the list hash L that every coefficient a_i is derived from
+fn hash_keys<T: CurveGroup, D: Digest>(pk_list: &[T]) -> Output<D> {
+    let pk_bytes: Vec<_> = pk_list.iter().map(GroupEncoding::to_bytes).collect();
+    let inputs: Vec<&[u8]> = pk_bytes.iter().map(AsRef::as_ref).collect();
+    tagged_hash::<D>("KeyAgg list", &inputs)
+}
+
+/// Hashes the list hash L and an individual's public key with the tag "KeyAgg coefficient", giving its coefficient a_i
+fn hash_agg<T: CurveGroup, D: Digest>(l: &[u8], pk: T) -> <T as Group>::Scalar {
+    hash_to_scalar(&tagged_hash::<D>(
+        "KeyAgg coefficient",
+        &[l, pk.to_bytes().as_ref()],
+    ))
+}
+
+/// Hashes the aggregate nonce points, the x coordinate of the aggregate key and a message with the tag "MuSig/noncecoef", giving the nonce coefficient b.
+/// An identity nonce point is encoded as zero bytes.
+fn hash_non<T: CurveGroup, D: Digest>(x: T, r_1: T, r_2: T, m: &[u8]) -> <T as Group>::Scalar {
+    let r_1_bytes = r_1.to_bytes();
+    let r_2_bytes = r_2.to_bytes();
+    hash_to_scalar(&tagged_hash::<D>(
+        "MuSig/noncecoef",
+        &[r_1_bytes.as_ref(), r_2_bytes.as_ref(), &x.x(), m],
+    ))
+}
+
+/// Hashes the x coordinates of R and the aggregate key and a message with the tag "BIP0340/challenge", giving the challenge e
+fn hash_sig<T: CurveGroup, D: Digest>(x: T, r: T, m: &[u8]) -> <T as Group>::Scalar {
+    hash_to_scalar(&tagged_hash::<D>("BIP0340/challenge", &[&r.x(), &x.x(), m]))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::bytes_from_hex;
     use k256::ProjectivePoint;
     use rand::distributions::Alphanumeric;
-    use rand::Rng;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use sha2::Sha512;
 
     #[test]
     fn musig_test_true() {
@@ -327,22 +1093,14 @@ mod test {
             .take(length)
             .map(char::from)
             .collect();
-        let message2 = message.into_iter().collect::<String>();
-        message2
+        message.into_iter().collect::<String>()
     }
 
     /// Generate random signer for testing
-    fn generate_random_signer<T: Group + GroupEncoding>() -> Signer<T> {
-        let rng1 = rand::thread_rng();
-        let rng2 = rand::thread_rng();
-        let sk = <T::Scalar as Field>::random(rng1);
-        Signer {
-            sk: sk,
-            pk: T::generator() * sk,
-            r: <T::Scalar as Field>::random(rng2),
-        }
+    fn generate_random_signer<T: CurveGroup>() -> Signer<T> {
+        Signer::random()
     }
-    
+
     /// Test that a signature verifies when it its message is passed intact through the verifier
     fn musig_test_true_aux() {
         let message_str = get_random_message(10);
@@ -352,15 +1110,15 @@ mod test {
         for _ in 1..num_signers {
             signers.push(generate_random_signer::<ProjectivePoint>());
         }
-        let pk_list = signers.iter().map(|signer| signer.pk()).collect();
-        let mut musig = MuSig::<ProjectivePoint>::new(&signers[..], message);
-        let signature = musig.sign();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let mut musig = MuSig::<ProjectivePoint>::new(&mut signers[..], message);
+        let signature = musig.sign().unwrap();
 
-        let verifier = verify(signature, pk_list, message);
-        assert_eq!(verifier, true);
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let verifier = verify(&signature, &key_agg, message);
+        assert!(verifier.is_ok());
     }
 
-
     /// Test that a signature fails to verify when its message is altered
     fn musig_test_false_aux() {
         let message_str = get_random_message(10);
@@ -370,13 +1128,1218 @@ mod test {
         for _ in 1..num_signers {
             signers.push(generate_random_signer::<ProjectivePoint>());
         }
-        let pk_list = signers.iter().map(|signer| signer.pk()).collect();
-        let mut musig = MuSig::<ProjectivePoint>::new(&signers[..], message);
-        let signature = musig.sign();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let mut musig = MuSig::<ProjectivePoint>::new(&mut signers[..], message);
+        let signature = musig.sign().unwrap();
 
         let message_altered_str = get_random_message(11);
         let message_altered = message_altered_str.as_bytes();
-        let verifier = verify(signature, pk_list, message_altered);
-        assert_eq!(verifier, false);
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let verifier = verify(&signature, &key_agg, message_altered);
+        assert_eq!(verifier, Err(Error::InvalidSignature));
+    }
+
+    /// Test that signing and verification work with a different hash function, and that verifying with the wrong one fails
+    #[test]
+    fn musig_test_sha512() {
+        let message_str = get_random_message(10);
+        let message = message_str.as_bytes();
+        let mut signers: Vec<_> = (0..5)
+            .map(|_| Signer::<ProjectivePoint, Sha512>::random())
+            .collect();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let mut musig = MuSig::<ProjectivePoint, Sha512>::new(&mut signers[..], message);
+        let signature = musig.sign().unwrap();
+
+        let key_agg = KeyAggContext::<ProjectivePoint, Sha512>::new(&pk_list).unwrap();
+        assert!(verify(&signature, &key_agg, message).is_ok());
+        let key_agg = KeyAggContext::<ProjectivePoint, Sha256>::new(&pk_list).unwrap();
+        assert!(verify(&signature, &key_agg, message).is_err());
+    }
+
+    /// Test that a signature with s = 0 and an r that is not an x coordinate on the curve is rejected instead of panicking
+    #[test]
+    fn musig_test_degenerate_signature() {
+        let message = b"identity";
+        let pk_list: Vec<_> = (0..3)
+            .map(|_| KeyPair::<ProjectivePoint>::random().public_key())
+            .collect();
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let signature = Signature::from_bytes(&[0; 64]).unwrap();
+        assert_eq!(
+            verify(&signature, &key_agg, message),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    /// Test that signers drawn from the same seeded RNG produce the same signature
+    #[test]
+    fn musig_test_seeded_rng() {
+        let message = b"seeded";
+        let sign = || {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut signers: Vec<_> = (0..4)
+                .map(|_| Signer::<ProjectivePoint>::random_with_rng(&mut rng))
+                .collect();
+            let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+            let signature = MuSig::<ProjectivePoint>::new(&mut signers[..], message)
+                .sign()
+                .unwrap();
+            let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+            assert!(verify(&signature, &key_agg, message).is_ok());
+            signature
+        };
+        assert_eq!(sign(), sign());
+    }
+
+    /// Test that nonces preprocessed for existing key pairs before the message is chosen produce a valid signature
+    #[test]
+    fn musig_test_preprocessed_nonces() {
+        let key_pairs: Vec<_> = (0..4)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let mut signers: Vec<_> = key_pairs.into_iter().map(Signer::preprocess).collect();
+
+        let message_str = get_random_message(10);
+        let message = message_str.as_bytes();
+        let signature = MuSig::<ProjectivePoint>::new(&mut signers[..], message)
+            .sign()
+            .unwrap();
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        assert!(verify(&signature, &key_agg, message).is_ok());
+    }
+
+    /// Test that R is the aggregate nonce R_1 + b * R_2, with b bound to the message
+    #[test]
+    fn musig_test_nonce_aggregation() {
+        let mut signers: Vec<_> = (0..3)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let (r_point_1, r_point_2) = signers.iter().map(|s| s.nonce_points().unwrap()).fold(
+            (ProjectivePoint::IDENTITY, ProjectivePoint::IDENTITY),
+            |(acc_1, acc_2), (r_1, r_2)| (acc_1 + r_1, acc_2 + r_2),
+        );
+
+        let mut musig = MuSig::<ProjectivePoint>::new(&mut signers[..], b"nonces");
+        let m = R0::from(&mut musig)
+            .round_1()
+            .unwrap()
+            .round_2()
+            .unwrap()
+            .round_3()
+            .unwrap()
+            .0;
+        assert_eq!(m.aggregate_nonce, (r_point_1, r_point_2));
+        let b = hash_non::<ProjectivePoint, Sha256>(m.x, r_point_1, r_point_2, b"nonces");
+        let signature = m.signature.clone().unwrap();
+        assert_eq!(signature.r(), (r_point_1 + r_point_2 * b).x());
+        assert_ne!(
+            b,
+            hash_non::<ProjectivePoint, Sha256>(m.x, r_point_1, r_point_2, b"other")
+        );
+    }
+
+    /// Run a distributed session where every message passes through its byte encoding, as it would over a network
+    fn run_distributed(
+        key_pairs: Vec<KeyPair<ProjectivePoint>>,
+        message: &[u8],
+    ) -> Result<Signature<ProjectivePoint>, Error> {
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let key_agg = KeyAggContext::new(&pk_list)?;
+        let session_id = SessionId::random();
+        let mut coordinator = Coordinator::<ProjectivePoint>::new(&key_agg, session_id, message);
+
+        let mut sessions = Vec::new();
+        let mut nonce_bytes = Vec::new();
+        for (index, key_pair) in key_pairs.into_iter().enumerate() {
+            let (session, nonce_message) =
+                SignerSession::<ProjectivePoint>::new(key_pair, &key_agg, index, session_id, None)?;
+            sessions.push(session);
+            nonce_bytes.push(nonce_message.to_bytes());
+        }
+
+        for bytes in &nonce_bytes {
+            let nonce_message = NonceMessage::from_bytes(bytes)?;
+            coordinator.receive_nonce(&nonce_message)?;
+            for session in sessions.iter_mut() {
+                if session.index() != nonce_message.index() {
+                    session.receive_nonce(&nonce_message)?;
+                }
+            }
+        }
+
+        for session in sessions {
+            let bytes = session.sign(message)?.to_bytes();
+            coordinator.receive_partial_signature(&PartialSignatureMessage::from_bytes(&bytes)?)?;
+        }
+        coordinator.signature()
+    }
+
+    #[test]
+    fn musig_test_distributed() {
+        for _ in 1..20 {
+            let num_signers = rand::thread_rng().gen_range(1..10);
+            let key_pairs: Vec<_> = (0..num_signers)
+                .map(|_| KeyPair::<ProjectivePoint>::random())
+                .collect();
+            let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+            let message_str = get_random_message(10);
+            let message = message_str.as_bytes();
+
+            let signature = run_distributed(key_pairs, message).unwrap();
+            let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+            assert!(verify(&signature, &key_agg, message).is_ok());
+            assert_eq!(
+                verify(&signature, &key_agg, b"other"),
+                Err(Error::InvalidSignature)
+            );
+        }
+    }
+
+    /// Test that sessions reject messages from unknown signers, duplicates, and finishing a round early
+    #[test]
+    fn musig_test_distributed_errors() {
+        let key_pairs: Vec<_> = (0..3)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let key_agg = KeyAggContext::new(&pk_list).unwrap();
+        let session_id = SessionId::random();
+
+        assert_eq!(
+            SignerSession::<ProjectivePoint>::new(KeyPair::random(), &key_agg, 0, session_id, None)
+                .unwrap_err(),
+            Error::UnknownSigner
+        );
+        assert_eq!(
+            SignerSession::<ProjectivePoint>::new(
+                key_pairs[0].clone(),
+                &key_agg,
+                1,
+                session_id,
+                None
+            )
+            .unwrap_err(),
+            Error::UnknownSigner
+        );
+
+        let (session, nonce_message) = SignerSession::<ProjectivePoint>::new(
+            key_pairs[0].clone(),
+            &key_agg,
+            0,
+            session_id,
+            None,
+        )
+        .unwrap();
+        let (mut other, other_nonce_message) = SignerSession::<ProjectivePoint>::new(
+            key_pairs[1].clone(),
+            &key_agg,
+            1,
+            session_id,
+            None,
+        )
+        .unwrap();
+        assert_eq!(other.index(), 1);
+        assert_eq!(
+            other.receive_nonce(&other_nonce_message),
+            Err(Error::DuplicateMessage)
+        );
+        let mut bytes = nonce_message.to_bytes();
+        bytes[35] = 3;
+        assert_eq!(
+            other.receive_nonce(&NonceMessage::from_bytes(&bytes).unwrap()),
+            Err(Error::UnknownSigner)
+        );
+        other.receive_nonce(&nonce_message).unwrap();
+        assert_eq!(session.sign(b"early").unwrap_err(), Error::MissingMessages);
+
+        let mut coordinator = Coordinator::<ProjectivePoint>::new(&key_agg, session_id, b"early");
+        coordinator.receive_nonce(&nonce_message).unwrap();
+        assert_eq!(coordinator.signature().unwrap_err(), Error::MissingMessages);
+    }
+
+    /// Test that a local signer's nonces sign once, and that a second session fails until they are refreshed
+    #[test]
+    fn musig_test_nonce_reuse() {
+        let mut signers: Vec<_> = (0..3)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        MuSig::<ProjectivePoint>::new(&mut signers[..], b"first")
+            .sign()
+            .unwrap();
+        assert_eq!(signers[0].nonce_points(), Err(Error::NonceReused));
+        assert_eq!(
+            MuSig::<ProjectivePoint>::new(&mut signers[..], b"second")
+                .sign()
+                .unwrap_err(),
+            Error::NonceReused
+        );
+
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let session_id = SessionId::random();
+        for signer in signers.iter_mut() {
+            signer.refresh_nonce(&key_agg, session_id);
+        }
+        let signature = MuSig::<ProjectivePoint>::new(&mut signers[..], b"second")
+            .sign()
+            .unwrap();
+        assert!(verify(&signature, &key_agg, b"second").is_ok());
+    }
+
+    /// Test that derived nonces depend on the randomness and on each of the secret key, aggregate key, session ID and message
+    #[test]
+    fn musig_test_nonce_derivation() {
+        let secret_key = SecretKey::<ProjectivePoint>::random();
+        let aggregate_key = Some(KeyPair::<ProjectivePoint>::random().public_key());
+        let session_id = SessionId::random();
+        let nonce_points = |secret_key: &SecretKey<_>,
+                            aggregate_key,
+                            session_id: Option<&SessionId>,
+                            message,
+                            seed| {
+            SecretNonce::generate::<Sha256>(
+                secret_key,
+                aggregate_key,
+                session_id,
+                message,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .nonce_points()
+        };
+
+        let points = nonce_points(&secret_key, aggregate_key, Some(&session_id), Some(b"m"), 1);
+        assert_ne!(points.0, points.1);
+        assert_eq!(
+            nonce_points(&secret_key, aggregate_key, Some(&session_id), Some(b"m"), 1),
+            points
+        );
+        let others = [
+            nonce_points(&secret_key, aggregate_key, Some(&session_id), Some(b"m"), 2),
+            nonce_points(
+                &SecretKey::random(),
+                aggregate_key,
+                Some(&session_id),
+                Some(b"m"),
+                1,
+            ),
+            nonce_points(&secret_key, None, Some(&session_id), Some(b"m"), 1),
+            nonce_points(&secret_key, aggregate_key, None, Some(b"m"), 1),
+            nonce_points(&secret_key, aggregate_key, Some(&session_id), Some(b"n"), 1),
+            nonce_points(&secret_key, aggregate_key, Some(&session_id), Some(b""), 1),
+            nonce_points(&secret_key, aggregate_key, Some(&session_id), None, 1),
+        ];
+        for other in others {
+            assert_ne!(other, points);
+        }
+    }
+
+    /// Test that refreshed nonces are bound to the aggregate key, the session ID and the hash function, and preprocessed ones to the hash function
+    #[test]
+    fn musig_test_refresh_nonce_context() {
+        let key_pairs: Vec<_> = (0..2)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let other_key_agg = key_agg.tweak(Field::random(rand::thread_rng())).unwrap();
+        let session_id = SessionId::random();
+        let mut signer = Signer::preprocess(key_pairs[0].clone());
+        let mut nonce_points = |key_agg: &KeyAggContext<_, _>, session_id| {
+            signer.refresh_nonce_with_rng(key_agg, session_id, &mut StdRng::seed_from_u64(1));
+            signer.nonce_points().unwrap()
+        };
+
+        let points = nonce_points(&key_agg, session_id);
+        assert_eq!(nonce_points(&key_agg, session_id), points);
+        assert_ne!(nonce_points(&other_key_agg, session_id), points);
+        assert_ne!(nonce_points(&key_agg, SessionId::random()), points);
+        let mut sha512_signer = Signer::<ProjectivePoint, Sha512>::preprocess(key_pairs[0].clone());
+        let sha512_key_agg = KeyAggContext::<ProjectivePoint, Sha512>::new(&pk_list).unwrap();
+        sha512_signer.refresh_nonce_with_rng(
+            &sha512_key_agg,
+            session_id,
+            &mut StdRng::seed_from_u64(1),
+        );
+        assert_ne!(sha512_signer.nonce_points().unwrap(), points);
+
+        // Preprocessed nonces are derived with the signer's hash function too
+        let preprocessed = |sha512: bool| {
+            let mut rng = StdRng::seed_from_u64(2);
+            if sha512 {
+                Signer::<ProjectivePoint, Sha512>::preprocess_with_rng(
+                    key_pairs[0].clone(),
+                    &mut rng,
+                )
+                .nonce_points()
+            } else {
+                Signer::<ProjectivePoint>::preprocess_with_rng(key_pairs[0].clone(), &mut rng)
+                    .nonce_points()
+            }
+        };
+        assert_eq!(preprocessed(false), preprocessed(false));
+        assert_ne!(preprocessed(false), preprocessed(true));
+    }
+
+    /// Test that a session started with a message refuses to sign any other message
+    #[test]
+    fn musig_test_bound_message() {
+        let key_pairs: Vec<_> = (0..2)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let key_agg = KeyAggContext::new(&pk_list).unwrap();
+        let session_id = SessionId::random();
+        let start = |message| {
+            let mut sessions = Vec::new();
+            let mut nonce_messages = Vec::new();
+            for (index, key_pair) in key_pairs.iter().enumerate() {
+                let (session, nonce_message) = SignerSession::<ProjectivePoint>::new(
+                    key_pair.clone(),
+                    &key_agg,
+                    index,
+                    session_id,
+                    message,
+                )
+                .unwrap();
+                sessions.push(session);
+                nonce_messages.push(nonce_message);
+            }
+            for session in sessions.iter_mut() {
+                for nonce_message in &nonce_messages {
+                    if session.index() != nonce_message.index() {
+                        session.receive_nonce(nonce_message).unwrap();
+                    }
+                }
+            }
+            sessions
+        };
+
+        let mut sessions = start(Some(b"bound"));
+        assert_eq!(
+            sessions.pop().unwrap().sign(b"other").unwrap_err(),
+            Error::SessionMismatch
+        );
+        assert!(sessions.pop().unwrap().sign(b"bound").is_ok());
+        // Without a bound message any message can be signed
+        assert!(start(None).pop().unwrap().sign(b"other").is_ok());
+    }
+
+    /// Test that round messages from another session are rejected by signers and the coordinator
+    #[test]
+    fn musig_test_session_mismatch() {
+        let key_pairs: Vec<_> = (0..2)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let key_agg = KeyAggContext::new(&pk_list).unwrap();
+        let (session_id, stale_id) = (SessionId::random(), SessionId::random());
+
+        let (mut session, nonce_message) = SignerSession::<ProjectivePoint>::new(
+            key_pairs[0].clone(),
+            &key_agg,
+            0,
+            session_id,
+            None,
+        )
+        .unwrap();
+        let (mut stale, stale_nonce_message) = SignerSession::<ProjectivePoint>::new(
+            key_pairs[1].clone(),
+            &key_agg,
+            1,
+            stale_id,
+            None,
+        )
+        .unwrap();
+        assert_eq!(stale_nonce_message.session_id(), stale_id);
+        assert_eq!(
+            session.receive_nonce(&stale_nonce_message),
+            Err(Error::SessionMismatch)
+        );
+
+        let mut coordinator = Coordinator::new(&key_agg, session_id, b"stale");
+        coordinator.receive_nonce(&nonce_message).unwrap();
+        assert_eq!(
+            coordinator.receive_nonce(&stale_nonce_message),
+            Err(Error::SessionMismatch)
+        );
+
+        // A partial signature left over from the stale session is rejected even though its signer index is still open
+        stale
+            .receive_nonce(&NonceMessage {
+                session_id: stale_id,
+                ..nonce_message.clone()
+            })
+            .unwrap();
+        let stale_partial_signature = stale.sign(b"stale").unwrap();
+        assert_eq!(
+            coordinator.receive_partial_signature(&stale_partial_signature),
+            Err(Error::SessionMismatch)
+        );
+    }
+
+    #[test]
+    fn musig_test_message_encoding() {
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let key_agg = KeyAggContext::new(&[key_pair.public_key()]).unwrap();
+        let session_id = SessionId::random();
+        let (session, nonce_message) =
+            SignerSession::<ProjectivePoint>::new(key_pair, &key_agg, 0, session_id, None).unwrap();
+        let bytes = nonce_message.to_bytes();
+        assert_eq!(bytes.len(), 36 + 2 * 33);
+        assert_eq!(NonceMessage::from_bytes(&bytes), Ok(nonce_message));
+        assert_eq!(
+            NonceMessage::<ProjectivePoint>::from_bytes(&bytes[1..]),
+            Err(Error::InvalidEncoding)
+        );
+        let mut identity = bytes.clone();
+        identity[36..69].copy_from_slice(&ProjectivePoint::IDENTITY.to_bytes());
+        assert_eq!(
+            NonceMessage::<ProjectivePoint>::from_bytes(&identity),
+            Err(Error::IdentityPoint)
+        );
+
+        let partial_signature = session.sign(b"encoding").unwrap();
+        let bytes = partial_signature.to_bytes();
+        assert_eq!(bytes.len(), 36 + 32);
+        assert_eq!(
+            PartialSignatureMessage::from_bytes(&bytes),
+            Ok(partial_signature)
+        );
+        let mut too_large = bytes.clone();
+        too_large[36..].fill(0xff);
+        assert_eq!(
+            PartialSignatureMessage::<ProjectivePoint>::from_bytes(&too_large),
+            Err(Error::InvalidEncoding)
+        );
+    }
+
+    /// Decode a compressed point, with 33 zero bytes standing for the identity as in BIP-327
+    fn point_from_hex(hex: &str) -> ProjectivePoint {
+        let bytes = bytes_from_hex(hex);
+        if bytes.iter().all(|byte| *byte == 0) {
+            return ProjectivePoint::IDENTITY;
+        }
+        point_from_bytes(&bytes).unwrap()
+    }
+
+    /// Decode a nonce pair R_1 || R_2 of two compressed points
+    fn nonce_from_hex(hex: &str) -> (ProjectivePoint, ProjectivePoint) {
+        (point_from_hex(&hex[..66]), point_from_hex(&hex[66..]))
+    }
+
+    fn key_agg_from_hex(pks: &[&str]) -> KeyAggContext<ProjectivePoint> {
+        let pk_list: Vec<_> = pks
+            .iter()
+            .map(|pk| PublicKey::from_point(point_from_hex(pk)).unwrap())
+            .collect();
+        KeyAggContext::new(&pk_list).unwrap()
+    }
+
+    /// The key_agg_vectors.json vectors of BIP-327
+    #[test]
+    fn musig_test_key_agg_vectors() {
+        let pks = [
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+        ];
+        let cases: [(&[usize], &str); 4] = [
+            (
+                &[0, 1, 2],
+                "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C",
+            ),
+            (
+                &[2, 1, 0],
+                "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B",
+            ),
+            (
+                &[0, 0, 0],
+                "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935",
+            ),
+            (
+                &[0, 0, 1, 1],
+                "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E",
+            ),
+        ];
+        for (key_indices, expected) in cases {
+            let pks: Vec<_> = key_indices.iter().map(|i| pks[*i]).collect();
+            assert_eq!(
+                key_agg_from_hex(&pks).x_only_aggregate_key().to_bytes(),
+                bytes_from_hex(expected),
+                "wrong aggregate key for keys {:?}",
+                key_indices
+            );
+        }
+    }
+
+    /// The nonce_agg_vectors.json vectors of BIP-327, including a sum that is the identity
+    #[test]
+    fn musig_test_nonce_agg_vectors() {
+        let pnonces = [
+            "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E66603BA47FBC1834437B3212E89A84D8425E7BF12E0245D98262268EBDCB385D50641",
+            "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60248C264CDD57D3C24D79990B0F865674EB62A0F9018277A95011B41BFC193B833",
+            "020151C80F435648DF67A22B749CD798CE54E0321D034B92B709B567D60A42E6660279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "03FF406FFD8ADB9CD29877E4985014F66A59F6CD01C0E88CAA8E5F3166B1F676A60379BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        ];
+        let cases = [
+            (
+                [0, 1],
+                "035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B024725377345BDE0E9C33AF3C43C0A29A9249F2F2956FA8CFEB55C8573D0262DC8",
+            ),
+            (
+                [2, 3],
+                "035FE1873B4F2967F52FEA4A06AD5A8ECCBE9D0FD73068012C894E2E87CCB5804B000000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        ];
+        for (nonce_indices, expected) in cases {
+            let nonce_points_vec: Vec<_> = nonce_indices
+                .iter()
+                .map(|i| nonce_from_hex(pnonces[*i]))
+                .collect();
+            let (r_point_1, r_point_2) = aggregate_nonce(&nonce_points_vec);
+            let encoded = [r_point_1.to_bytes(), r_point_2.to_bytes()].concat();
+            assert_eq!(encoded, bytes_from_hex(expected));
+        }
+    }
+
+    /// The sign_verify_vectors.json signing vectors of BIP-327. Each partial signature must match and pass partial signature verification.
+    #[test]
+    fn musig_test_sign_vectors() {
+        let sk = SecretKey::<ProjectivePoint>::from_scalar(hash_to_scalar(&bytes_from_hex(
+            "7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671",
+        )))
+        .unwrap();
+        let pks = [
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661",
+        ];
+        assert_eq!(sk.public_key().point(), point_from_hex(pks[0]));
+        let pnonce = nonce_from_hex("0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480");
+        let aggnonces = [
+            "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9",
+            // The sum of the signer's nonce and its negation: R falls back to the generator
+            "000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+        ];
+        let msgs = [
+            bytes_from_hex("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF"),
+            Vec::new(),
+            vec![0x26; 38],
+        ];
+        let cases: [(&[usize], usize, usize, &str); 6] = [
+            (
+                &[0, 1, 2],
+                0,
+                0,
+                "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB",
+            ),
+            (
+                &[1, 0, 2],
+                0,
+                0,
+                "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52",
+            ),
+            (
+                &[1, 2, 0],
+                0,
+                0,
+                "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900",
+            ),
+            (
+                &[0, 1],
+                1,
+                0,
+                "AE386064B26105404798F75DE2EB9AF5EDA5387B064B83D049CB7C5E08879531",
+            ),
+            (
+                &[0, 1, 2],
+                0,
+                1,
+                "D7D63FFD644CCDA4E62BC2BC0B1D02DD32A1DC3030E155195810231D1037D82D",
+            ),
+            (
+                &[0, 1, 2],
+                0,
+                2,
+                "E184351828DA5094A97C79CABDAAA0BFB87608C32E8829A4DF5340A6F243B78C",
+            ),
+        ];
+        for (key_indices, aggnonce_index, msg_index, expected) in cases {
+            let pks: Vec<_> = key_indices.iter().map(|i| pks[*i]).collect();
+            let key_agg = key_agg_from_hex(&pks);
+            let index = key_agg.index_of(sk.public_key()).unwrap();
+            let nonce = SecretNonce::<ProjectivePoint> {
+                r_1: SecretScalar(hash_to_scalar(&bytes_from_hex(
+                    "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61",
+                ))),
+                r_2: SecretScalar(hash_to_scalar(&bytes_from_hex(
+                    "FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7",
+                ))),
+            };
+            assert_eq!(nonce.nonce_points(), pnonce);
+
+            let values = challenge::<ProjectivePoint, Sha256>(
+                key_agg.aggregate_key().point(),
+                nonce_from_hex(aggnonces[aggnonce_index]),
+                &msgs[msg_index],
+            );
+            let a = key_agg.signing_coefficient(index);
+            let s = nonce.sign(&sk, values, a);
+            assert_eq!(
+                scalar_to_bytes(&s),
+                bytes_from_hex(expected),
+                "wrong partial signature for keys {:?}, aggregate nonce {} and message {}",
+                key_indices,
+                aggnonce_index,
+                msg_index
+            );
+            assert!(verify_partial_signatures(
+                &[s],
+                &[pnonce],
+                &[sk.public_key().point()],
+                &[a],
+                values
+            )
+            .is_ok());
+        }
+    }
+
+    /// The tweak_vectors.json vectors of BIP-327: partial signatures after chains of plain and x-only tweaks, and a tweak equal to the group order
+    #[test]
+    fn musig_test_tweak_vectors() {
+        let sk = SecretKey::<ProjectivePoint>::from_scalar(hash_to_scalar(&bytes_from_hex(
+            "7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671",
+        )))
+        .unwrap();
+        let key_agg = key_agg_from_hex(&[
+            "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9",
+        ]);
+        let index = key_agg.index_of(sk.public_key()).unwrap();
+        let aggnonce = nonce_from_hex("028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9");
+        let message =
+            bytes_from_hex("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF");
+        let tweaks = [
+            "E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB",
+            "AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455",
+            "F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0",
+            "1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D",
+        ];
+        let cases: [(&[usize], &[bool], &str); 5] = [
+            (
+                &[0],
+                &[true],
+                "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91",
+            ),
+            (
+                &[0],
+                &[false],
+                "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D",
+            ),
+            (
+                &[0, 1],
+                &[false, true],
+                "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408",
+            ),
+            (
+                &[0, 1, 2, 3],
+                &[false, false, true, true],
+                "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435",
+            ),
+            (
+                &[0, 1, 2, 3],
+                &[true, false, true, false],
+                "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239",
+            ),
+        ];
+        for (tweak_indices, x_only, expected) in cases {
+            let tweaked =
+                tweak_indices
+                    .iter()
+                    .zip(x_only)
+                    .fold(key_agg.clone(), |key_agg, (i, x_only)| {
+                        key_agg
+                            .tweak_from_bytes(&bytes_from_hex(tweaks[*i]), *x_only)
+                            .unwrap()
+                    });
+            let nonce = SecretNonce::<ProjectivePoint> {
+                r_1: SecretScalar(hash_to_scalar(&bytes_from_hex(
+                    "508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61",
+                ))),
+                r_2: SecretScalar(hash_to_scalar(&bytes_from_hex(
+                    "FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7",
+                ))),
+            };
+            let pnonce = nonce.nonce_points();
+            let values = challenge::<ProjectivePoint, Sha256>(
+                tweaked.aggregate_key().point(),
+                aggnonce,
+                &message,
+            );
+            let a = tweaked.signing_coefficient(index);
+            let s = nonce.sign(&sk, values, a);
+            assert_eq!(
+                scalar_to_bytes(&s),
+                bytes_from_hex(expected),
+                "wrong partial signature for tweaks {:?} with x-only {:?}",
+                tweak_indices,
+                x_only
+            );
+            assert!(verify_partial_signatures(
+                &[s],
+                &[pnonce],
+                &[sk.public_key().point()],
+                &[a],
+                values
+            )
+            .is_ok());
+        }
+
+        // A tweak equal to the group order is out of range
+        let order =
+            bytes_from_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141");
+        for x_only in [true, false] {
+            assert_eq!(
+                key_agg.tweak_from_bytes(&order, x_only).unwrap_err(),
+                Error::InvalidEncoding
+            );
+        }
+    }
+
+    /// Test that aggregated signatures, untweaked and after plain and x-only tweaks, are BIP-340 signatures for the x-only aggregate key
+    #[test]
+    fn musig_test_bip340_verify() {
+        use crate::bip340::BIP340Group;
+
+        for _ in 1..10 {
+            let mut signers: Vec<_> = (0..3)
+                .map(|_| generate_random_signer::<ProjectivePoint>())
+                .collect();
+            let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+            let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+            let mut rng = rand::thread_rng();
+            let tweaked = key_agg
+                .tweak(Field::random(&mut rng))
+                .unwrap()
+                .x_only_tweak(Field::random(&mut rng))
+                .unwrap();
+
+            let signature = MuSig::new_with_context(&mut signers[..], b"bip340", &key_agg)
+                .sign()
+                .unwrap();
+            let pk = key_agg.x_only_aggregate_key();
+            assert!(<ProjectivePoint as BIP340Group>::verify(&signature, b"bip340", &pk).is_ok());
+            let decoded = Signature::from_bytes(&signature.to_bytes()).unwrap();
+            assert!(<ProjectivePoint as BIP340Group>::verify(&decoded, b"bip340", &pk).is_ok());
+
+            let signature = sign_with_context(&signers, &tweaked, b"bip340").unwrap();
+            let pk = tweaked.x_only_aggregate_key();
+            assert!(<ProjectivePoint as BIP340Group>::verify(&signature, b"bip340", &pk).is_ok());
+            assert_eq!(
+                <ProjectivePoint as BIP340Group>::verify(&signature, b"other", &pk),
+                Err(Error::InvalidSignature)
+            );
+            assert!(<ProjectivePoint as BIP340Group>::verify(
+                &signature,
+                b"bip340",
+                &key_agg.x_only_aggregate_key()
+            )
+            .is_err());
+        }
+    }
+
+    /// Test that round 3 names the signer whose partial signature does not match the nonce points it published
+    #[test]
+    fn musig_test_blame_round_3() {
+        let mut signers: Vec<_> = (0..4)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let mut musig = MuSig::<ProjectivePoint>::new(&mut signers[..], b"blame");
+        let r2 = R0::from(&mut musig).round_1().unwrap().round_2().unwrap();
+        r2.0.nonce_points_vec[2].1 += ProjectivePoint::GENERATOR;
+        assert_eq!(
+            r2.round_3().err(),
+            Some(Error::InvalidPartialSignatures(vec![2]))
+        );
+    }
+
+    /// Test that the coordinator names every signer that sent a wrong partial signature
+    #[test]
+    fn musig_test_blame_coordinator() {
+        let key_pairs: Vec<_> = (0..5)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+        let key_agg = KeyAggContext::new(&pk_list).unwrap();
+        let session_id = SessionId::random();
+        let message = b"blame";
+        let mut coordinator = Coordinator::<ProjectivePoint>::new(&key_agg, session_id, message);
+
+        let mut sessions = Vec::new();
+        let mut nonce_messages = Vec::new();
+        for (index, key_pair) in key_pairs.into_iter().enumerate() {
+            let (session, nonce_message) =
+                SignerSession::<ProjectivePoint>::new(key_pair, &key_agg, index, session_id, None)
+                    .unwrap();
+            sessions.push(session);
+            nonce_messages.push(nonce_message);
+        }
+        for nonce_message in &nonce_messages {
+            coordinator.receive_nonce(nonce_message).unwrap();
+            for session in sessions.iter_mut() {
+                if session.index() != nonce_message.index() {
+                    session.receive_nonce(nonce_message).unwrap();
+                }
+            }
+        }
+
+        for session in sessions {
+            let mut bytes = session.sign(message).unwrap().to_bytes();
+            // Signers 1 and 3 send garbage
+            if bytes[35] == 1 || bytes[35] == 3 {
+                bytes[50] ^= 1;
+            }
+            let partial_signature = PartialSignatureMessage::from_bytes(&bytes).unwrap();
+            coordinator
+                .receive_partial_signature(&partial_signature)
+                .unwrap();
+        }
+        assert_eq!(
+            coordinator.signature().unwrap_err(),
+            Error::InvalidPartialSignatures(vec![1, 3])
+        );
+    }
+
+    /// Test that the context's X and a_i match the definitions, and that one context serves signing and verification
+    #[test]
+    fn musig_test_key_agg_context() {
+        let mut signers: Vec<_> = (0..4)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+
+        let points: Vec<_> = pk_list.iter().map(PublicKey::point).collect();
+        let l = hash_keys::<ProjectivePoint, Sha256>(&points);
+        let mut x = ProjectivePoint::IDENTITY;
+        for (i, pk) in points.iter().enumerate() {
+            // The second key gets the coefficient 1
+            let a = if i == 1 {
+                <ProjectivePoint as Group>::Scalar::ONE
+            } else {
+                hash_agg::<ProjectivePoint, Sha256>(&l, *pk)
+            };
+            assert_eq!(key_agg.coefficient(i), Some(a));
+            assert_eq!(key_agg.index_of(pk_list[i]), Some(i));
+            x += pk * &a;
+        }
+        assert_eq!(key_agg.aggregate_key().point(), x);
+        assert_eq!(key_agg.coefficient(4), None);
+        assert_eq!(key_agg.index_of(KeyPair::random().public_key()), None);
+
+        let signature = MuSig::<ProjectivePoint>::new(&mut signers[..], b"context")
+            .sign()
+            .unwrap();
+        assert!(
+            verify_aggregated::<_, Sha256>(&signature, key_agg.aggregate_key(), b"context").is_ok()
+        );
+    }
+
+    /// Test that with sorting any order of the same keys gives the same aggregate key, and without it the order matters
+    #[test]
+    fn musig_test_key_sort() {
+        let pk_list: Vec<_> = (0..5)
+            .map(|_| KeyPair::<ProjectivePoint>::random().public_key())
+            .collect();
+        let mut reversed = pk_list.clone();
+        reversed.reverse();
+
+        let sorted = key_sort(&pk_list);
+        assert_eq!(key_sort(&reversed), sorted);
+        assert!(sorted
+            .windows(2)
+            .all(|pair| pair[0].point().to_bytes() <= pair[1].point().to_bytes()));
+
+        let options = KeyAggOptions {
+            sort: true,
+            ..Default::default()
+        };
+        let key_agg =
+            KeyAggContext::<ProjectivePoint>::new_with_options(&pk_list, options).unwrap();
+        let reversed_key_agg =
+            KeyAggContext::<ProjectivePoint>::new_with_options(&reversed, options).unwrap();
+        assert_eq!(key_agg.aggregate_key(), reversed_key_agg.aggregate_key());
+        assert_eq!(key_agg.public_keys(), &sorted[..]);
+        assert_ne!(
+            KeyAggContext::<ProjectivePoint>::new(&pk_list)
+                .unwrap()
+                .aggregate_key(),
+            KeyAggContext::<ProjectivePoint>::new(&reversed)
+                .unwrap()
+                .aggregate_key()
+        );
+    }
+
+    /// Test that the second distinct key, and every copy of it, gets the coefficient 1
+    #[test]
+    fn musig_test_second_key_optimization() {
+        let first = KeyPair::<ProjectivePoint>::random().public_key();
+        let second = KeyPair::<ProjectivePoint>::random().public_key();
+        let third = KeyPair::<ProjectivePoint>::random().public_key();
+        let pk_list = [first, first, second, third, second];
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let options = KeyAggOptions {
+            second_key_optimization: false,
+            ..Default::default()
+        };
+        let plain = KeyAggContext::<ProjectivePoint>::new_with_options(&pk_list, options).unwrap();
+
+        let one = <ProjectivePoint as Group>::Scalar::ONE;
+        assert_eq!(key_agg.coefficient(2), Some(one));
+        assert_eq!(key_agg.coefficient(4), Some(one));
+        for i in [0, 1, 3] {
+            assert_eq!(key_agg.coefficient(i), plain.coefficient(i));
+            assert_ne!(key_agg.coefficient(i), Some(one));
+        }
+        assert_ne!(key_agg.aggregate_key(), plain.aggregate_key());
+    }
+
+    /// Test that `verify` checks against the context's own aggregate key when the second key optimization is off
+    #[test]
+    fn musig_test_verify_without_second_key_optimization() {
+        let options = KeyAggOptions {
+            second_key_optimization: false,
+            ..Default::default()
+        };
+        let mut signers: Vec<_> = (0..3)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let plain = KeyAggContext::<ProjectivePoint>::new_with_options(&pk_list, options).unwrap();
+        let signature =
+            MuSig::<ProjectivePoint>::new_with_options(&mut signers[..], b"plain", options)
+                .sign()
+                .unwrap();
+        assert!(verify(&signature, &plain, b"plain").is_ok());
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        assert_eq!(
+            verify(&signature, &key_agg, b"plain"),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    /// Test that signing with both options gives a signature that verifies against the context's aggregate key, whatever the signers' order
+    #[test]
+    fn musig_test_sign_with_key_agg_options() {
+        let options = KeyAggOptions {
+            sort: true,
+            second_key_optimization: true,
+        };
+        let mut signers: Vec<_> = (0..4)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let mut pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        pk_list.reverse();
+        let key_agg =
+            KeyAggContext::<ProjectivePoint>::new_with_options(&pk_list, options).unwrap();
+
+        let signature =
+            MuSig::<ProjectivePoint>::new_with_options(&mut signers[..], b"options", options)
+                .sign()
+                .unwrap();
+        assert!(
+            verify_aggregated::<_, Sha256>(&signature, key_agg.aggregate_key(), b"options").is_ok()
+        );
+
+        let session_id = SessionId::random();
+        let mut coordinator = Coordinator::<ProjectivePoint>::new(&key_agg, session_id, b"options");
+        let mut sessions = Vec::new();
+        for signer in &signers {
+            let (session, nonce_message) = SignerSession::<ProjectivePoint>::new(
+                signer.key_pair.clone(),
+                &key_agg,
+                key_agg.index_of(signer.public_key()).unwrap(),
+                session_id,
+                None,
+            )
+            .unwrap();
+            coordinator.receive_nonce(&nonce_message).unwrap();
+            sessions.push((session, nonce_message));
+        }
+        let nonce_messages: Vec<_> = sessions
+            .iter()
+            .map(|(_, message)| message.clone())
+            .collect();
+        for (session, _) in sessions.iter_mut() {
+            for nonce_message in &nonce_messages {
+                if session.index() != nonce_message.index() {
+                    session.receive_nonce(nonce_message).unwrap();
+                }
+            }
+        }
+        for (session, _) in sessions {
+            coordinator
+                .receive_partial_signature(&session.sign(b"options").unwrap())
+                .unwrap();
+        }
+        let signature = coordinator.signature().unwrap();
+        assert!(
+            verify_aggregated::<_, Sha256>(&signature, key_agg.aggregate_key(), b"options").is_ok()
+        );
+        assert!(verify(&signature, &key_agg, b"options").is_ok());
+    }
+
+    /// Sign with a context through the distributed session and return the signature
+    fn sign_with_context(
+        signers: &[Signer<ProjectivePoint>],
+        key_agg: &KeyAggContext<ProjectivePoint>,
+        message: &[u8],
+    ) -> Result<Signature<ProjectivePoint>, Error> {
+        let session_id = SessionId::random();
+        let mut coordinator = Coordinator::new(key_agg, session_id, message);
+        let mut sessions = Vec::new();
+        let mut nonce_messages = Vec::new();
+        for signer in signers {
+            let index = key_agg
+                .index_of(signer.public_key())
+                .ok_or(Error::UnknownSigner)?;
+            let (session, nonce_message) =
+                SignerSession::new(signer.key_pair.clone(), key_agg, index, session_id, None)?;
+            coordinator.receive_nonce(&nonce_message)?;
+            sessions.push(session);
+            nonce_messages.push(nonce_message);
+        }
+        for session in sessions.iter_mut() {
+            for nonce_message in &nonce_messages {
+                if session.index() != nonce_message.index() {
+                    session.receive_nonce(nonce_message)?;
+                }
+            }
+        }
+        for session in sessions {
+            coordinator.receive_partial_signature(&session.sign(message)?)?;
+        }
+        coordinator.signature()
+    }
+
+    #[test]
+    fn musig_test_plain_tweak() {
+        let mut signers: Vec<_> = (0..3)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let t = <ProjectivePoint as Group>::Scalar::random(rand::thread_rng());
+        let tweaked = key_agg.tweak(t).unwrap();
+        assert_eq!(
+            tweaked.aggregate_key().point(),
+            key_agg.aggregate_key().point() + ProjectivePoint::GENERATOR * t
+        );
+        assert_eq!(tweaked.tweak_accumulator(), t);
+
+        let signature = MuSig::new_with_context(&mut signers[..], b"tweak", &tweaked)
+            .sign()
+            .unwrap();
+        assert!(verify(&signature, &tweaked, b"tweak").is_ok());
+        assert_eq!(
+            verify(&signature, &key_agg, b"tweak"),
+            Err(Error::InvalidSignature)
+        );
+
+        let signature = sign_with_context(&signers, &tweaked, b"tweak").unwrap();
+        assert!(verify(&signature, &tweaked, b"tweak").is_ok());
+    }
+
+    /// Test x-only tweaks for aggregate keys with both parities of y, where an odd y negates the key before tweaking
+    #[test]
+    fn musig_test_x_only_tweak() {
+        let one = <ProjectivePoint as Group>::Scalar::ONE;
+        let (mut seen_odd, mut seen_even) = (false, false);
+        while !(seen_odd && seen_even) {
+            let signers: Vec<_> = (0..3)
+                .map(|_| generate_random_signer::<ProjectivePoint>())
+                .collect();
+            let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+            let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+            let x = key_agg.aggregate_key().point();
+            let odd = x.y_is_odd();
+            seen_odd |= odd;
+            seen_even |= !odd;
+
+            let t = <ProjectivePoint as Group>::Scalar::random(rand::thread_rng());
+            let tweaked = key_agg.x_only_tweak(t).unwrap();
+            let even_x = if odd { -x } else { x };
+            assert_eq!(
+                tweaked.aggregate_key().point(),
+                even_x + ProjectivePoint::GENERATOR * t
+            );
+            assert_eq!(tweaked.parity_accumulator(), if odd { -one } else { one });
+
+            let signature = sign_with_context(&signers, &tweaked, b"x-only").unwrap();
+            assert!(verify(&signature, &tweaked, b"x-only").is_ok());
+        }
+    }
+
+    /// Test a chain of plain and x-only tweaks, as when a Taproot output key is derived from a tweaked internal key
+    #[test]
+    fn musig_test_chained_tweaks() {
+        for _ in 1..20 {
+            let mut signers: Vec<_> = (0..3)
+                .map(|_| generate_random_signer::<ProjectivePoint>())
+                .collect();
+            let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+            let mut rng = rand::thread_rng();
+            let tweaked = KeyAggContext::<ProjectivePoint>::new(&pk_list)
+                .unwrap()
+                .tweak(Field::random(&mut rng))
+                .unwrap()
+                .x_only_tweak(Field::random(&mut rng))
+                .unwrap()
+                .x_only_tweak(Field::random(&mut rng))
+                .unwrap();
+
+            let signature = sign_with_context(&signers, &tweaked, b"chain").unwrap();
+            assert!(verify(&signature, &tweaked, b"chain").is_ok());
+            let signature = MuSig::new_with_context(&mut signers[..], b"chain", &tweaked)
+                .sign()
+                .unwrap();
+            assert!(verify(&signature, &tweaked, b"chain").is_ok());
+        }
+    }
+
+    /// Test that MuSig signatures can be batched together with plain Schnorr signatures, and that a bad one is found
+    #[test]
+    fn musig_test_batch_with_schnorr() {
+        use crate::schnorr::{verify_equations, SchnorrGroup};
+
+        let message = b"batch";
+        let mut signers: Vec<_> = (0..3)
+            .map(|_| generate_random_signer::<ProjectivePoint>())
+            .collect();
+        let pk_list: Vec<_> = signers.iter().map(Signer::public_key).collect();
+        let signature = MuSig::<ProjectivePoint>::new(&mut signers[..], message)
+            .sign()
+            .unwrap();
+
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let schnorr_signature =
+            <ProjectivePoint as SchnorrGroup>::sign(key_pair.secret_key(), "batch").unwrap();
+        let schnorr_equation = <ProjectivePoint as SchnorrGroup>::equation(
+            schnorr_signature,
+            key_pair.public_key(),
+            "batch",
+        );
+
+        let key_agg = KeyAggContext::<ProjectivePoint>::new(&pk_list).unwrap();
+        let good = equation(&signature, &key_agg, message).unwrap();
+        let bad = equation(&signature, &key_agg, b"other").unwrap();
+        assert!(verify_equations(&[schnorr_equation, good]).is_ok());
+        assert_eq!(
+            verify_equations(&[schnorr_equation, bad, good]),
+            Err(Error::InvalidBatch(vec![1]))
+        );
     }
 }
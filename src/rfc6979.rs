@@ -0,0 +1,93 @@
+use elliptic_curve::PrimeField;
+use hmac::{Mac, SimpleHmac};
+use sha2::digest::{core_api::BlockSizeUser, Digest};
+use zeroize::Zeroizing;
+
+use crate::{hash_to_scalar, modulus_bytes, scalar_to_bytes};
+
+// Deterministic generation of the ECDSA nonce k as described in RFC 6979 section 3.2
+// https://www.rfc-editor.org/rfc/rfc6979#section-3.2
+
+/// Take the leftmost `qlen` bits of `bytes` and return them as a big-endian integer of `ceil(qlen / 8)` bytes (RFC 6979 section 2.3.2)
+pub fn bits2int(bytes: &[u8], qlen: usize) -> Vec<u8> {
+    let rlen = qlen.div_ceil(8);
+    let mut res = vec![0u8; rlen];
+    if bytes.len() * 8 <= qlen {
+        // Input is no longer than the order, so it is used as is
+        res[rlen - bytes.len()..].copy_from_slice(bytes);
+        return res;
+    }
+    res.copy_from_slice(&bytes[..rlen]);
+    // Drop the bits past qlen that came along with the last whole byte
+    let shift = rlen * 8 - qlen;
+    if shift > 0 {
+        for i in (0..rlen).rev() {
+            let carry = if i > 0 { res[i - 1] << (8 - shift) } else { 0 };
+            res[i] = (res[i] >> shift) | carry;
+        }
+    }
+    res
+}
+
+/// HMAC with the key K over the concatenation of `inputs`
+fn hmac<D: Digest + BlockSizeUser>(key: &[u8], inputs: &[&[u8]]) -> Vec<u8> {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    for input in inputs {
+        mac.update(input);
+    }
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Generate the nonce k from the secret key x and the hashed message z = bits2int(H(m)) mod q.
+/// `extra` is the optional additional data k' from RFC 6979 section 3.6; pass fresh randomness here for hedged signatures.
+pub fn generate_k<F: PrimeField, D: Digest + BlockSizeUser>(x: &F, z: &F, extra: &[u8]) -> F {
+    let qlen = F::NUM_BITS as usize;
+    let hlen = <D as Digest>::output_size();
+    let modulus = modulus_bytes::<F>();
+
+    // int2octets(x) and bits2octets(h1); z has already been reduced modulo q
+    let x = Zeroizing::new(scalar_to_bytes(x));
+    let z = scalar_to_bytes(z);
+
+    // Steps b and c
+    let mut v = vec![0x01u8; hlen];
+    let mut k = vec![0x00u8; hlen];
+
+    // Steps d through g
+    k = hmac::<D>(&k, &[&v, &[0x00], &x, &z, extra]);
+    v = hmac::<D>(&k, &[&v]);
+    k = hmac::<D>(&k, &[&v, &[0x01], &x, &z, extra]);
+    v = hmac::<D>(&k, &[&v]);
+
+    // Step h
+    loop {
+        let mut t = Vec::new();
+        while t.len() * 8 < qlen {
+            v = hmac::<D>(&k, &[&v]);
+            t.extend_from_slice(&v);
+        }
+        let candidate = bits2int(&t, qlen);
+        // Accept the candidate if 1 <= k < q, comparing as big-endian integers of equal length
+        if candidate.iter().any(|byte| *byte != 0) && candidate < modulus {
+            return hash_to_scalar::<F>(&candidate);
+        }
+        k = hmac::<D>(&k, &[&v, &[0x00]]);
+        v = hmac::<D>(&k, &[&v]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bits2int_test() {
+        // Shorter than qlen: unchanged, left padded
+        assert_eq!(bits2int(&[0xab], 12), vec![0x00, 0xab]);
+        // Whole bytes: truncated to the leftmost qlen bits
+        assert_eq!(bits2int(&[0xab, 0xcd, 0xef], 16), vec![0xab, 0xcd]);
+        // Partial bytes: the leftmost 12 bits of 0xabcdef are 0xabc
+        assert_eq!(bits2int(&[0xab, 0xcd, 0xef], 12), vec![0x0a, 0xbc]);
+        assert_eq!(bits2int(&[0xff, 0xff], 9), vec![0x01, 0xff]);
+    }
+}
@@ -1,77 +1,276 @@
-use elliptic_curve::{group::GroupEncoding, Field, Group, PrimeField};
-use elliptic_curves::hash;
+use elliptic_curve::{group::GroupEncoding, Field, Group};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
 
-    /// Trait requiring functions to implement Schnorr signatures.
-pub trait SchnorrGroup {
+use crate::{hash_to_scalar, msm::msm, Error, PublicKey, SecretKey};
 
-    /// Scalar type representing the scalar field of the elliptic curve being used to generate the signatures
-    type Scalar: PrimeField;
+/// Trait requiring functions to implement Schnorr signatures.
+/// D is the hash function used for the challenge e = D(D(message) || pk || R), SHA-256 unless another is given (e.g. SHA-512 for Ed25519-style signatures).
+/// As the message enters the challenge only through its digest, signing a message, a hasher holding it, or its digest all give the same signature.
+/// Keys are the shared `SecretKey` and `PublicKey` types, e.g. from `KeyPair::random()`.
+pub trait SchnorrGroup<D: Digest = Sha256>: Group {
+    /// Creates signature given a scalar and a message
+    fn sign(sk: &SecretKey<Self>, message: &str) -> Result<(Self::Scalar, Self), Error>;
 
-    /// Generates private key (random scalar)
-    fn generate_private_key() -> Self::Scalar;
+    /// Like `sign`, drawing the nonce r from the given RNG instead of the thread-local one
+    fn sign_with_rng(
+        sk: &SecretKey<Self>,
+        message: &str,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self::Scalar, Self), Error>;
 
-    /// Generates public key (private key * generator)
-    fn generate_public_key(sk: Self::Scalar) -> Self;
+    /// Creates signature from a hasher that the message has been streamed into. This gives the same signature as `sign` on the whole message.
+    fn sign_digest(sk: &SecretKey<Self>, digest: D) -> Result<(Self::Scalar, Self), Error>;
 
-    /// Creates signature given a scalar and a message
-    fn sign(sk: Self::Scalar, message: &str) -> (Self::Scalar, Self);
+    /// Like `sign_digest`, drawing the nonce r from the given RNG
+    fn sign_digest_with_rng(
+        sk: &SecretKey<Self>,
+        digest: D,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self::Scalar, Self), Error>;
+
+    /// Creates signature given the digest D(message) of a message, the same signature as `sign` on the message itself
+    fn sign_prehash(sk: &SecretKey<Self>, prehash: &[u8]) -> Result<(Self::Scalar, Self), Error>;
+
+    /// Like `sign_prehash`, drawing the nonce r from the given RNG
+    fn sign_prehash_with_rng(
+        sk: &SecretKey<Self>,
+        prehash: &[u8],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self::Scalar, Self), Error>;
+
+    /// Verify given a Schnorr signature, public key, and message; returns an error describing why the signature is rejected if it is not valid
+    fn verify(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        message: &str,
+    ) -> Result<(), Error>;
+
+    /// Verify given a Schnorr signature, public key, and a hasher that the message has been streamed into
+    fn verify_digest(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        digest: D,
+    ) -> Result<(), Error>;
+
+    /// Verify given a Schnorr signature, public key, and the digest D(message) of the message
+    fn verify_prehash(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        prehash: &[u8],
+    ) -> Result<(), Error>;
+
+    /// The equation s * G == R + c * pk that `verify` checks for a signature, to be batched with `verify_equations`
+    fn equation(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        message: &str,
+    ) -> Equation<Self>;
+
+    /// Verify many signatures at once with a random linear combination, which is much faster than verifying them one by one.
+    /// If the batch fails, fails with `Error::InvalidBatch` holding the indices of the signatures that do not verify.
+    fn verify_batch(batch: &[BatchItem<Self>]) -> Result<(), Error>;
+
+    /// Like `verify_batch`, drawing the random weights from the given RNG
+    fn verify_batch_with_rng(
+        batch: &[BatchItem<Self>],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(), Error>;
+}
+
+/// A signature (s, R) with the public key and message it is checked against in `verify_batch`
+pub type BatchItem<'a, G> = ((<G as Group>::Scalar, G), PublicKey<G>, &'a str);
+
+/// The Schnorr verification equation s * G == R + c * X with the challenge c already computed.
+/// Plain Schnorr and MuSig signatures both reduce to it, so they can be verified together in one batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Equation<G: Group> {
+    s: G::Scalar,
+    r_point: G,
+    c: G::Scalar,
+    x: G,
+}
+
+impl<G: Group> Equation<G> {
+    /// Create the equation s * G == R + c * X
+    pub fn new(s: G::Scalar, r_point: G, c: G::Scalar, x: G) -> Self {
+        Equation { s, r_point, c, x }
+    }
+
+    /// Returns true if the equation holds
+    pub fn holds(&self) -> bool {
+        // s * G - R - c * X is the identity exactly when the equation holds
+        msm(&[
+            (self.s, G::generator()),
+            (-G::Scalar::ONE, self.r_point),
+            (-self.c, self.x),
+        ]) == G::identity()
+    }
+}
 
-    /// Verify given a Schnorr signature, public key, and message
-    fn verify(signature: (Self::Scalar, Self), pk: Self, message: &str) -> bool;
+/// Check all equations with one random linear combination: sum(a_i * s_i) * G == sum(a_i * R_i) + sum(a_i * c_i * X_i) for random a_i.
+/// A batch containing a false equation passes with negligible probability. On failure the equations are checked one by one
+/// and the error is `Error::InvalidBatch` with the indices of the false ones.
+pub fn verify_equations<G: Group>(equations: &[Equation<G>]) -> Result<(), Error> {
+    verify_equations_with_rng(equations, &mut rand::thread_rng())
+}
 
+/// Like `verify_equations`, drawing the random weights from the given RNG
+pub fn verify_equations_with_rng<G: Group>(
+    equations: &[Equation<G>],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(), Error> {
+    // One multi-scalar multiplication of sum(a_i * s_i) * G - sum(a_i * R_i) - sum(a_i * c_i * X_i)
+    let mut s_sum = G::Scalar::ZERO;
+    let mut terms = Vec::with_capacity(2 * equations.len() + 1);
+    for equation in equations {
+        let a = G::Scalar::random(&mut *rng);
+        s_sum += a * equation.s;
+        terms.push((-a, equation.r_point));
+        terms.push((-(a * equation.c), equation.x));
+    }
+    terms.push((s_sum, G::generator()));
+    if msm(&terms) == G::identity() {
+        return Ok(());
+    }
+    let bad = equations
+        .iter()
+        .enumerate()
+        .filter(|(_, equation)| !equation.holds())
+        .map(|(i, _)| i)
+        .collect();
+    Err(Error::InvalidBatch(bad))
 }
 
-impl<T: Group + GroupEncoding> SchnorrGroup for T {
-    type Scalar = <Self as Group>::Scalar;
-    fn generate_private_key() -> Self::Scalar {
-        // generate random scalar
-        let rng = rand::thread_rng();
-        <Self::Scalar as Field>::random(rng)
+impl<T: Group + GroupEncoding, D: Digest> SchnorrGroup<D> for T {
+    fn sign(sk: &SecretKey<Self>, message: &str) -> Result<(Self::Scalar, Self), Error> {
+        <Self as SchnorrGroup<D>>::sign_with_rng(sk, message, &mut rand::thread_rng())
+    }
+
+    fn sign_with_rng(
+        sk: &SecretKey<Self>,
+        message: &str,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self::Scalar, Self), Error> {
+        <Self as SchnorrGroup<D>>::sign_digest_with_rng(sk, D::new_with_prefix(message), rng)
+    }
+
+    fn sign_digest(sk: &SecretKey<Self>, digest: D) -> Result<(Self::Scalar, Self), Error> {
+        <Self as SchnorrGroup<D>>::sign_digest_with_rng(sk, digest, &mut rand::thread_rng())
+    }
+
+    fn sign_digest_with_rng(
+        sk: &SecretKey<Self>,
+        digest: D,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self::Scalar, Self), Error> {
+        <Self as SchnorrGroup<D>>::sign_prehash_with_rng(sk, &digest.finalize(), rng)
     }
 
-    fn generate_public_key(sk: Self::Scalar) -> T {
-        // public key = secret key * generator
-        Self::generator() * sk
+    fn sign_prehash(sk: &SecretKey<Self>, prehash: &[u8]) -> Result<(Self::Scalar, Self), Error> {
+        <Self as SchnorrGroup<D>>::sign_prehash_with_rng(sk, prehash, &mut rand::thread_rng())
     }
 
-    fn sign(sk: Self::Scalar, message: &str) -> (Self::Scalar, Self) {
+    fn sign_prehash_with_rng(
+        sk: &SecretKey<Self>,
+        prehash: &[u8],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(Self::Scalar, Self), Error> {
         // r is a random scalar to be regenerated for each signature
-        let r = Self::generate_private_key();
+        let r = <Self::Scalar as Field>::random(rng);
         // r_point = r * generator
-        let r_point = Self::generate_public_key(r);
-        // convert to bytes for hashing
-        let r_point_bytes = r_point.to_bytes();
-        let pk = Self::generate_public_key(sk).to_bytes();
+        let r_point = Self::generator() * r;
+        let pk = sk.public_key();
 
-        let inputs = vec![message.as_bytes(), pk.as_ref(), r_point_bytes.as_ref()];
-        let hash = hash::<T>(inputs);
+        let hash = challenge::<Self, D>(prehash, pk, r_point);
 
-        let s = r + sk * hash;
+        let s = r + hash * sk.scalar();
         // signature is s and r_point
-        (s, r_point)
+        Ok((s, r_point))
+    }
+
+    fn verify(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        message: &str,
+    ) -> Result<(), Error> {
+        <Self as SchnorrGroup<D>>::verify_digest(signature, pk, D::new_with_prefix(message))
+    }
+
+    fn verify_digest(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        digest: D,
+    ) -> Result<(), Error> {
+        <Self as SchnorrGroup<D>>::verify_prehash(signature, pk, &digest.finalize())
     }
 
-    fn verify(signature: (Self::Scalar, Self), pk: Self, message: &str) -> bool {
+    fn verify_prehash(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        prehash: &[u8],
+    ) -> Result<(), Error> {
         let (s, r_point) = signature;
-        // convert to bytes for hashing
-        let (pk_bytes, r_point_bytes) = (pk.to_bytes(), r_point.to_bytes());
-        let inputs = vec![
-            message.as_bytes(),
-            pk_bytes.as_ref(),
-            r_point_bytes.as_ref(),
-        ];
-        let hash = hash::<T>(inputs);
+        let hash = challenge::<Self, D>(prehash, pk, r_point);
         // s * G = (r + sk*hash) * G = (r * G) + (sk * G * hash) = r_point + pk * hash
-        return Self::generator() * s == (r_point + pk * hash);
+        if !Equation::new(s, r_point, hash, pk.point()).holds() {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    fn equation(
+        signature: (Self::Scalar, Self),
+        pk: PublicKey<Self>,
+        message: &str,
+    ) -> Equation<Self> {
+        let (s, r_point) = signature;
+        let hash = challenge::<Self, D>(&D::digest(message), pk, r_point);
+        Equation::new(s, r_point, hash, pk.point())
+    }
+
+    fn verify_batch(batch: &[BatchItem<Self>]) -> Result<(), Error> {
+        <Self as SchnorrGroup<D>>::verify_batch_with_rng(batch, &mut rand::thread_rng())
+    }
+
+    fn verify_batch_with_rng(
+        batch: &[BatchItem<Self>],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(), Error> {
+        let equations: Vec<_> = batch
+            .iter()
+            .map(|(signature, pk, message)| {
+                <Self as SchnorrGroup<D>>::equation(*signature, *pk, message)
+            })
+            .collect();
+        verify_equations_with_rng(&equations, rng)
     }
 }
 
+/// The challenge hash D(prehash || pk || R), where prehash = D(message)
+fn challenge<T: Group + GroupEncoding, D: Digest>(
+    prehash: &[u8],
+    pk: PublicKey<T>,
+    r_point: T,
+) -> T::Scalar {
+    // convert to bytes for hashing
+    let hash = D::new()
+        .chain_update(prehash)
+        .chain_update(pk.point().to_bytes())
+        .chain_update(r_point.to_bytes())
+        .finalize();
+    hash_to_scalar::<T::Scalar>(&hash)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::KeyPair;
     use k256::ProjectivePoint;
     use rand::distributions::Alphanumeric;
-    use rand::{self, Rng};
+    use rand::rngs::StdRng;
+    use rand::{self, Rng, SeedableRng};
+    use sha2::Sha512;
 
     #[test]
     fn schnorr_test_true() {
@@ -87,38 +286,167 @@ mod test {
         }
     }
 
-    // generates random string of characters to test signatures 
+    // signs and verifies with SHA-512 instead of the default, and checks that the default hash does not accept the signature
+    #[test]
+    fn schnorr_test_sha512() {
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let (sk, pk) = (key_pair.secret_key(), key_pair.public_key());
+        let message_string = get_random_message(10);
+        let message = message_string.as_str();
+        let signature = <ProjectivePoint as SchnorrGroup<Sha512>>::sign(sk, message).unwrap();
+        assert!(<ProjectivePoint as SchnorrGroup<Sha512>>::verify(signature, pk, message).is_ok());
+        assert_eq!(
+            <ProjectivePoint as SchnorrGroup>::verify(signature, pk, message),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    // checks that signing the whole message, streaming it into a hasher, or signing its digest all give signatures that verify every way
+    #[test]
+    fn schnorr_test_digest() {
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let (sk, pk) = (key_pair.secret_key(), key_pair.public_key());
+        let message_string = get_random_message(100);
+        let message = message_string.as_str();
+
+        let mut digest = Sha256::new();
+        for chunk in message.as_bytes().chunks(7) {
+            digest.update(chunk);
+        }
+        let signature = ProjectivePoint::sign_digest(sk, digest.clone()).unwrap();
+        assert!(<ProjectivePoint as SchnorrGroup>::verify(signature, pk, message).is_ok());
+        let signature = <ProjectivePoint as SchnorrGroup>::sign(sk, message).unwrap();
+        assert!(ProjectivePoint::verify_digest(signature, pk, digest).is_ok());
+
+        let prehash = Sha256::digest(message.as_bytes());
+        let signature = <ProjectivePoint as SchnorrGroup>::sign_prehash(sk, &prehash).unwrap();
+        assert!(<ProjectivePoint as SchnorrGroup>::verify_prehash(signature, pk, &prehash).is_ok());
+        assert!(<ProjectivePoint as SchnorrGroup>::verify(signature, pk, message).is_ok());
+        let signature = <ProjectivePoint as SchnorrGroup>::sign(sk, message).unwrap();
+        assert!(<ProjectivePoint as SchnorrGroup>::verify_prehash(signature, pk, &prehash).is_ok());
+        assert_eq!(
+            <ProjectivePoint as SchnorrGroup>::verify_prehash(
+                signature,
+                pk,
+                &Sha256::digest(b"other")
+            ),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn schnorr_test_batch() {
+        for _ in 1..20 {
+            schnorr_test_batch_aux()
+        }
+    }
+
+    // verifies a batch of random signatures, then corrupts some of them and checks that exactly those are reported
+    fn schnorr_test_batch_aux() {
+        let messages: Vec<String> = (0..20).map(|_| get_random_message(10)).collect();
+        let key_pairs: Vec<_> = (0..20)
+            .map(|_| KeyPair::<ProjectivePoint>::random())
+            .collect();
+        let mut batch: Vec<_> = key_pairs
+            .iter()
+            .zip(&messages)
+            .map(|(key_pair, message)| {
+                let signature =
+                    <ProjectivePoint as SchnorrGroup>::sign(key_pair.secret_key(), message)
+                        .unwrap();
+                (signature, key_pair.public_key(), message.as_str())
+            })
+            .collect();
+        assert!(<ProjectivePoint as SchnorrGroup>::verify_batch(&batch).is_ok());
+        assert!(<ProjectivePoint as SchnorrGroup>::verify_batch(&[]).is_ok());
+
+        let mut bad: Vec<usize> = (0..3)
+            .map(|_| rand::thread_rng().gen_range(0..20))
+            .collect();
+        bad.sort();
+        bad.dedup();
+        for &i in &bad {
+            // swap in a different key, which invalidates the signature
+            batch[i].1 = key_pairs[(i + 1) % 20].public_key();
+        }
+        assert_eq!(
+            <ProjectivePoint as SchnorrGroup>::verify_batch(&batch),
+            Err(Error::InvalidBatch(bad))
+        );
+    }
+
+    // checks that signing with a seeded RNG is reproducible
+    #[test]
+    fn schnorr_test_seeded_rng() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let key_pair = KeyPair::<ProjectivePoint>::random_with_rng(&mut rng);
+        let sk = key_pair.secret_key();
+        let sign = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            <ProjectivePoint as SchnorrGroup>::sign_with_rng(sk, "message", &mut rng).unwrap()
+        };
+        assert_eq!(sign(4), sign(4));
+        assert_ne!(sign(4), sign(5));
+        assert!(<ProjectivePoint as SchnorrGroup>::verify(
+            sign(4),
+            key_pair.public_key(),
+            "message"
+        )
+        .is_ok());
+    }
+
+    // checks that keys which would make every signature valid cannot be constructed, and that the trivial signature is rejected
+    #[test]
+    fn schnorr_test_degenerate_keys() {
+        let zero = <ProjectivePoint as Group>::Scalar::ZERO;
+        assert_eq!(
+            SecretKey::<ProjectivePoint>::from_scalar(zero).unwrap_err(),
+            Error::ZeroScalar
+        );
+        // With pk = 0 the equation s * G = R would hold for any message
+        assert_eq!(
+            PublicKey::from_point(ProjectivePoint::IDENTITY),
+            Err(Error::IdentityPoint)
+        );
+        let pk = KeyPair::<ProjectivePoint>::random().public_key();
+        let signature = (zero, ProjectivePoint::IDENTITY);
+        assert_eq!(
+            <ProjectivePoint as SchnorrGroup>::verify(signature, pk, "message"),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    // generates random string of characters to test signatures
     fn get_random_message(length: usize) -> String {
         let message: Vec<char> = rand::thread_rng()
             .sample_iter(&Alphanumeric)
             .take(length)
             .map(char::from)
             .collect();
-        let message2 = message.into_iter().collect::<String>();
-        message2
+        message.into_iter().collect::<String>()
     }
 
     // passes unaltered randomly generated signature and message through verifier and checks if it returns true
     fn schnorr_test_true_aux() {
-        let sk = ProjectivePoint::generate_private_key();
-        let pk = ProjectivePoint::generate_public_key(sk);
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let (sk, pk) = (key_pair.secret_key(), key_pair.public_key());
         let message_string = get_random_message(10);
         let message = message_string.as_str();
-        let signature = ProjectivePoint::sign(sk, message);
-        let verifier = ProjectivePoint::verify(signature, pk, message);
-        assert_eq!(verifier, true);
+        let signature = <ProjectivePoint as SchnorrGroup>::sign(sk, message).unwrap();
+        let verifier = <ProjectivePoint as SchnorrGroup>::verify(signature, pk, message);
+        assert!(verifier.is_ok());
     }
 
     // passes altered message through verifier and checks if it returns false
     fn schnorr_test_false_aux() {
-        let sk = ProjectivePoint::generate_private_key();
-        let pk = ProjectivePoint::generate_public_key(sk);
+        let key_pair = KeyPair::<ProjectivePoint>::random();
+        let (sk, pk) = (key_pair.secret_key(), key_pair.public_key());
         let message_string = get_random_message(10);
         let message = message_string.as_str();
         let message_altered_string = get_random_message(15);
         let message_altered = message_altered_string.as_str();
-        let signature = ProjectivePoint::sign(sk, message);
-        let verifier = ProjectivePoint::verify(signature, pk, message_altered);
-        assert_eq!(verifier, false);
+        let signature = <ProjectivePoint as SchnorrGroup>::sign(sk, message).unwrap();
+        let verifier = <ProjectivePoint as SchnorrGroup>::verify(signature, pk, message_altered);
+        assert_eq!(verifier, Err(Error::InvalidSignature));
     }
 }
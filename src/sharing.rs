@@ -0,0 +1,416 @@
+use elliptic_curve::{Field, Group};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+
+use crate::{msm::msm, Error};
+
+// Shamir secret sharing: the secret s is the constant term of a random polynomial f of degree t - 1, and participant i gets the share f(i).
+// Any t shares determine f, and with it s = f(0), by Lagrange interpolation, while fewer than t shares reveal nothing about s.
+// Verifiable secret sharing publishes commitments to the coefficients of f so that every participant can check its share.
+// Feldman commitments C_k = a_k * G reveal s * G. Pedersen commitments C_k = a_k * G + b_k * H hide s, with the b_k the coefficients
+// of a second random polynomial f' and H a second generator; every participant then also gets f'(i).
+// Participants are identified by 1..=n, since f(0) is the secret itself.
+
+/// A participant's share (i, f(i)) of a secret. The value is never printed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share<G: Group> {
+    identifier: usize,
+    value: G::Scalar,
+}
+
+impl<G: Group> Share<G> {
+    /// Create the share f(i) = value of participant i = identifier, e.g. to restore it from storage
+    pub fn new(identifier: usize, value: G::Scalar) -> Self {
+        Share { identifier, value }
+    }
+
+    /// Returns the identifier i of the participant
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Returns the value f(i) of the share
+    pub fn value(&self) -> G::Scalar {
+        self.value
+    }
+}
+
+impl<G: Group> fmt::Debug for Share<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("identifier", &self.identifier)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+/// A participant's share of a Pedersen sharing: the share f(i) of the secret and the share f'(i) of the blinding polynomial
+#[derive(Clone, PartialEq, Eq)]
+pub struct PedersenShare<G: Group> {
+    share: Share<G>,
+    blinding: G::Scalar,
+}
+
+impl<G: Group> PedersenShare<G> {
+    /// Create a Pedersen share from the share of the secret and the value f'(i) of the blinding polynomial
+    pub fn new(share: Share<G>, blinding: G::Scalar) -> Self {
+        PedersenShare { share, blinding }
+    }
+
+    /// Returns the share f(i) of the secret
+    pub fn share(&self) -> &Share<G> {
+        &self.share
+    }
+
+    /// Returns the value f'(i) of the blinding polynomial
+    pub fn blinding(&self) -> G::Scalar {
+        self.blinding
+    }
+}
+
+impl<G: Group> fmt::Debug for PedersenShare<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PedersenShare")
+            .field("share", &self.share)
+            .field("blinding", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Split a secret into shares for `participants` participants, any `threshold` of which reconstruct it.
+/// Fails with `Error::InvalidThreshold` unless 1 <= threshold <= participants.
+pub fn split<G: Group>(
+    secret: G::Scalar,
+    threshold: usize,
+    participants: usize,
+) -> Result<Vec<Share<G>>, Error> {
+    split_with_rng(secret, threshold, participants, &mut rand::thread_rng())
+}
+
+/// Like `split`, drawing the coefficients of the polynomial from the given RNG
+pub fn split_with_rng<G: Group>(
+    secret: G::Scalar,
+    threshold: usize,
+    participants: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Vec<Share<G>>, Error> {
+    check_parameters(threshold, participants)?;
+    let coefficients = random_polynomial(secret, threshold, rng);
+    Ok(shares(&coefficients, participants))
+}
+
+/// Reconstruct the secret f(0) from shares by Lagrange interpolation. Any `threshold` shares give the secret, and fewer give a meaningless value.
+/// Fails with `Error::MissingMessages` for no shares, and with `Error::InvalidShares` naming identifiers that are 0 or appear more than once.
+pub fn reconstruct<G: Group>(shares: &[Share<G>]) -> Result<G::Scalar, Error> {
+    if shares.is_empty() {
+        return Err(Error::MissingMessages);
+    }
+    let identifiers: Vec<usize> = shares.iter().map(Share::identifier).collect();
+    shares
+        .iter()
+        .map(|share| Ok(lagrange_coefficient::<G>(share.identifier, &identifiers)? * share.value))
+        .sum()
+}
+
+/// Split a secret like `split`, and also return the Feldman commitments C_k = a_k * G to the coefficients of the polynomial, against which
+/// every share can be checked with `feldman_verify`. C_0 = secret * G is public.
+pub fn feldman_split<G: Group>(
+    secret: G::Scalar,
+    threshold: usize,
+    participants: usize,
+) -> Result<(Vec<Share<G>>, Vec<G>), Error> {
+    feldman_split_with_rng(secret, threshold, participants, &mut rand::thread_rng())
+}
+
+/// Like `feldman_split`, drawing the coefficients of the polynomial from the given RNG
+pub fn feldman_split_with_rng<G: Group>(
+    secret: G::Scalar,
+    threshold: usize,
+    participants: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(Vec<Share<G>>, Vec<G>), Error> {
+    check_parameters(threshold, participants)?;
+    let coefficients = random_polynomial(secret, threshold, rng);
+    let commitments = coefficients
+        .iter()
+        .map(|coefficient| G::generator() * coefficient)
+        .collect();
+    Ok((shares(&coefficients, participants), commitments))
+}
+
+/// Check a share against Feldman commitments: f(i) * G == sum(C_k * i^k). Fails with `Error::InvalidShares` naming the share's participant otherwise.
+pub fn feldman_verify<G: Group>(share: &Share<G>, commitments: &[G]) -> Result<(), Error> {
+    if share.identifier == 0
+        || G::generator() * share.value != feldman_public_share(commitments, share.identifier)
+    {
+        return Err(Error::InvalidShares(vec![share.identifier]));
+    }
+    Ok(())
+}
+
+/// The point f(i) * G = sum(C_k * i^k) that the share of participant i must match, computed from the Feldman commitments alone
+pub fn feldman_public_share<G: Group>(commitments: &[G], identifier: usize) -> G {
+    evaluate_commitments(commitments, identifier_scalar::<G>(identifier))
+}
+
+/// Split a secret with Pedersen commitments C_k = a_k * G + b_k * H, which reveal nothing about the secret, and return the shares and
+/// commitments to check them against with `pedersen_verify`. H must be a generator whose discrete logarithm to G nobody knows, such as one
+/// from hashing to the curve; whoever knows it can open the commitments to other polynomials.
+pub fn pedersen_split<G: Group>(
+    secret: G::Scalar,
+    h: G,
+    threshold: usize,
+    participants: usize,
+) -> Result<(Vec<PedersenShare<G>>, Vec<G>), Error> {
+    pedersen_split_with_rng(secret, h, threshold, participants, &mut rand::thread_rng())
+}
+
+/// Like `pedersen_split`, drawing the coefficients of both polynomials from the given RNG
+pub fn pedersen_split_with_rng<G: Group>(
+    secret: G::Scalar,
+    h: G,
+    threshold: usize,
+    participants: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(Vec<PedersenShare<G>>, Vec<G>), Error> {
+    check_parameters(threshold, participants)?;
+    let coefficients = random_polynomial(secret, threshold, rng);
+    let blinding = random_polynomial(G::Scalar::random(&mut *rng), threshold, rng);
+    // The coefficients are secret, so the commitments use constant-time scalar multiplication rather than `msm`
+    let commitments = coefficients
+        .iter()
+        .zip(&blinding)
+        .map(|(a, b)| G::generator() * a + h * b)
+        .collect();
+    let pedersen_shares = shares(&coefficients, participants)
+        .into_iter()
+        .zip(shares::<G>(&blinding, participants))
+        .map(|(share, blinding)| PedersenShare::new(share, blinding.value))
+        .collect();
+    Ok((pedersen_shares, commitments))
+}
+
+/// Check a Pedersen share against the commitments: f(i) * G + f'(i) * H == sum(C_k * i^k). Fails with `Error::InvalidShares`
+/// naming the share's participant otherwise.
+pub fn pedersen_verify<G: Group>(
+    share: &PedersenShare<G>,
+    h: G,
+    commitments: &[G],
+) -> Result<(), Error> {
+    let identifier = share.share.identifier;
+    let expected = evaluate_commitments(commitments, identifier_scalar::<G>(identifier));
+    if identifier == 0 || G::generator() * share.share.value + h * share.blinding != expected {
+        return Err(Error::InvalidShares(vec![identifier]));
+    }
+    Ok(())
+}
+
+/// The Lagrange coefficient of participant i for interpolating f(0) from the shares of the given participants:
+/// the product of j / (j - i) over the other participants j. Fails with `Error::InvalidShares` naming identifiers that are 0 or repeated.
+pub fn lagrange_coefficient<G: Group>(
+    identifier: usize,
+    identifiers: &[usize],
+) -> Result<G::Scalar, Error> {
+    let mut invalid: Vec<usize> = identifiers
+        .iter()
+        .enumerate()
+        .filter(|(k, j)| **j == 0 || identifiers[..*k].contains(j))
+        .map(|(_, j)| *j)
+        .collect();
+    if identifier == 0 && !invalid.contains(&0) {
+        invalid.push(0);
+    }
+    if !invalid.is_empty() {
+        return Err(Error::InvalidShares(invalid));
+    }
+
+    let x_i = identifier_scalar::<G>(identifier);
+    let (numerator, denominator) = identifiers
+        .iter()
+        .filter(|j| **j != identifier)
+        .map(|j| identifier_scalar::<G>(*j))
+        .fold(
+            (G::Scalar::ONE, G::Scalar::ONE),
+            |(numerator, denominator), x_j| (numerator * x_j, denominator * (x_j - x_i)),
+        );
+    // The identifiers are distinct and nonzero, so the denominator is not zero
+    Ok(numerator * denominator.invert().expect("denominator is zero"))
+}
+
+/// Fail with `Error::InvalidThreshold` unless 1 <= threshold <= participants
+fn check_parameters(threshold: usize, participants: usize) -> Result<(), Error> {
+    if threshold == 0 || threshold > participants {
+        return Err(Error::InvalidThreshold);
+    }
+    Ok(())
+}
+
+fn identifier_scalar<G: Group>(identifier: usize) -> G::Scalar {
+    G::Scalar::from(identifier as u64)
+}
+
+/// A random polynomial of degree threshold - 1 with the given constant term, as its coefficients from the constant term up
+fn random_polynomial<F: Field>(
+    constant: F,
+    threshold: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Vec<F> {
+    let mut coefficients = vec![constant];
+    coefficients.extend((1..threshold).map(|_| F::random(&mut *rng)));
+    coefficients
+}
+
+/// The shares f(1), ..., f(participants) of the polynomial with the given coefficients
+fn shares<G: Group>(coefficients: &[G::Scalar], participants: usize) -> Vec<Share<G>> {
+    (1..=participants)
+        .map(|identifier| Share {
+            identifier,
+            value: evaluate(coefficients, identifier_scalar::<G>(identifier)),
+        })
+        .collect()
+}
+
+/// Evaluate a polynomial, given by its coefficients from the constant term up, at x with Horner's rule
+fn evaluate<F: Field>(coefficients: &[F], x: F) -> F {
+    coefficients
+        .iter()
+        .rev()
+        .fold(F::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+/// Evaluate sum(C_k * x^k) for commitments C_k to the coefficients of a polynomial
+fn evaluate_commitments<G: Group>(commitments: &[G], x: G::Scalar) -> G {
+    let mut power = G::Scalar::ONE;
+    let terms: Vec<(G::Scalar, G)> = commitments
+        .iter()
+        .map(|commitment| {
+            let term = (power, *commitment);
+            power *= x;
+            term
+        })
+        .collect();
+    msm(&terms)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k256::{ProjectivePoint, Scalar};
+    use rand::seq::SliceRandom;
+
+    #[test]
+    fn sharing_test_reconstruct() {
+        for (threshold, participants) in [(1, 1), (1, 4), (2, 3), (3, 5), (7, 10)] {
+            let secret = Scalar::random(rand::thread_rng());
+            let shares = split::<ProjectivePoint>(secret, threshold, participants).unwrap();
+            assert_eq!(shares.len(), participants);
+            assert_eq!(reconstruct(&shares), Ok(secret));
+
+            let mut chosen: Vec<_> = shares
+                .choose_multiple(&mut rand::thread_rng(), threshold)
+                .cloned()
+                .collect();
+            assert_eq!(reconstruct(&chosen), Ok(secret));
+            if threshold > 1 {
+                chosen.pop();
+                assert_ne!(reconstruct(&chosen), Ok(secret));
+            }
+        }
+    }
+
+    #[test]
+    fn sharing_test_errors() {
+        let secret = Scalar::random(rand::thread_rng());
+        assert_eq!(
+            split::<ProjectivePoint>(secret, 0, 3),
+            Err(Error::InvalidThreshold)
+        );
+        assert_eq!(
+            split::<ProjectivePoint>(secret, 4, 3),
+            Err(Error::InvalidThreshold)
+        );
+        assert_eq!(
+            reconstruct::<ProjectivePoint>(&[]),
+            Err(Error::MissingMessages)
+        );
+
+        let mut shares = split::<ProjectivePoint>(secret, 2, 3).unwrap();
+        shares[2] = Share::new(1, shares[2].value());
+        assert_eq!(reconstruct(&shares), Err(Error::InvalidShares(vec![1])));
+        shares[2] = Share::new(0, shares[2].value());
+        assert_eq!(reconstruct(&shares), Err(Error::InvalidShares(vec![0])));
+        assert!(!format!("{:?}", shares[0]).contains(&format!("{:?}", shares[0].value())));
+    }
+
+    #[test]
+    fn sharing_test_feldman() {
+        let secret = Scalar::random(rand::thread_rng());
+        let (shares, commitments) = feldman_split::<ProjectivePoint>(secret, 3, 5).unwrap();
+        assert_eq!(commitments.len(), 3);
+        assert_eq!(commitments[0], ProjectivePoint::GENERATOR * secret);
+        for share in &shares {
+            assert!(feldman_verify(share, &commitments).is_ok());
+            assert_eq!(
+                feldman_public_share(&commitments, share.identifier()),
+                ProjectivePoint::GENERATOR * share.value()
+            );
+        }
+        assert_eq!(reconstruct(&shares[1..4]), Ok(secret));
+
+        let bad = Share::new(2, shares[1].value() + Scalar::ONE);
+        assert_eq!(
+            feldman_verify(&bad, &commitments),
+            Err(Error::InvalidShares(vec![2]))
+        );
+        let moved = Share::new(3, shares[1].value());
+        assert_eq!(
+            feldman_verify(&moved, &commitments),
+            Err(Error::InvalidShares(vec![3]))
+        );
+    }
+
+    #[test]
+    fn sharing_test_pedersen() {
+        // A second generator for the test; in practice its discrete logarithm must be unknown
+        let h = ProjectivePoint::random(rand::thread_rng());
+        let secret = Scalar::random(rand::thread_rng());
+        let (shares, commitments) = pedersen_split::<ProjectivePoint>(secret, h, 2, 4).unwrap();
+        assert_ne!(commitments[0], ProjectivePoint::GENERATOR * secret);
+        for share in &shares {
+            assert!(pedersen_verify(share, h, &commitments).is_ok());
+        }
+        let secret_shares: Vec<_> = shares.iter().map(|share| share.share().clone()).collect();
+        assert_eq!(reconstruct(&secret_shares[2..]), Ok(secret));
+
+        let bad = PedersenShare::new(
+            shares[0].share().clone(),
+            shares[0].blinding() + Scalar::ONE,
+        );
+        assert_eq!(
+            pedersen_verify(&bad, h, &commitments),
+            Err(Error::InvalidShares(vec![1]))
+        );
+        assert_eq!(
+            pedersen_verify(&shares[0], ProjectivePoint::GENERATOR, &commitments),
+            Err(Error::InvalidShares(vec![1]))
+        );
+    }
+
+    #[test]
+    fn lagrange_coefficient_test() {
+        // Interpolating the line f(x) = 1 + x from x = 1 and x = 2 at 0: f(0) = 2 * f(1) - f(2)
+        let identifiers = [1, 2];
+        assert_eq!(
+            lagrange_coefficient::<ProjectivePoint>(1, &identifiers),
+            Ok(Scalar::from(2u64))
+        );
+        assert_eq!(
+            lagrange_coefficient::<ProjectivePoint>(2, &identifiers),
+            Ok(-Scalar::ONE)
+        );
+        assert_eq!(
+            lagrange_coefficient::<ProjectivePoint>(1, &[1, 2, 2, 0]),
+            Err(Error::InvalidShares(vec![2, 0]))
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use elliptic_curves::{
+    musig::{
+        verify, verify_aggregated, Coordinator, KeyAggContext, MuSig, NonceMessage,
+        PartialSignatureMessage, SessionId, Signature, Signer, SignerSession,
+    },
+    Error, KeyPair, PublicKey,
+};
+use k256::ProjectivePoint;
+use sha2::Sha256;
+
+/// Run a distributed session for the key pairs, which are in the order of `pk_list`, passing every message through its byte encoding
+fn sign_distributed(
+    key_pairs: Vec<KeyPair<ProjectivePoint>>,
+    pk_list: &[PublicKey<ProjectivePoint>],
+    message: &[u8],
+) -> Result<Signature<ProjectivePoint>, Error> {
+    let key_agg = KeyAggContext::new(pk_list)?;
+    let session_id = SessionId::random();
+    let mut coordinator = Coordinator::<ProjectivePoint>::new(&key_agg, session_id, message);
+    let mut sessions = Vec::new();
+    let mut nonce_bytes = Vec::new();
+    for (index, key_pair) in key_pairs.into_iter().enumerate() {
+        let (session, nonce_message) = SignerSession::<ProjectivePoint>::new(
+            key_pair,
+            &key_agg,
+            index,
+            session_id,
+            Some(message),
+        )?;
+        sessions.push(session);
+        nonce_bytes.push(nonce_message.to_bytes());
+    }
+
+    for bytes in &nonce_bytes {
+        let nonce_message = NonceMessage::from_bytes(bytes)?;
+        coordinator.receive_nonce(&nonce_message)?;
+        for session in sessions.iter_mut() {
+            if session.index() != nonce_message.index() {
+                session.receive_nonce(&nonce_message)?;
+            }
+        }
+    }
+
+    for session in sessions {
+        let bytes = session.sign(message)?.to_bytes();
+        coordinator.receive_partial_signature(&PartialSignatureMessage::from_bytes(&bytes)?)?;
+    }
+    coordinator.signature()
+}
+
+#[test]
+fn distributed_signature_verifies() {
+    let key_pairs: Vec<_> = (0..4)
+        .map(|_| KeyPair::<ProjectivePoint>::random())
+        .collect();
+    let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+    let message = b"distributed";
+
+    let signature = sign_distributed(key_pairs, &pk_list, message).unwrap();
+    let key_agg = KeyAggContext::<_, Sha256>::new(&pk_list).unwrap();
+    assert!(verify(&signature, &key_agg, message).is_ok());
+
+    let aggregate_key = key_agg.aggregate_key();
+    assert!(verify_aggregated::<_, Sha256>(&signature, aggregate_key, message).is_ok());
+    assert_eq!(
+        verify_aggregated::<_, Sha256>(&signature, aggregate_key, b"other"),
+        Err(Error::InvalidSignature)
+    );
+
+    let decoded = Signature::from_bytes(&signature.to_bytes()).unwrap();
+    assert_eq!(decoded, signature);
+}
+
+#[test]
+fn local_signers_from_existing_keys() {
+    let key_pairs: Vec<_> = (0..3)
+        .map(|_| KeyPair::<ProjectivePoint>::random())
+        .collect();
+    let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+    let mut signers: Vec<_> = key_pairs.into_iter().map(Signer::preprocess).collect();
+    let message = b"local";
+
+    let signature = MuSig::<ProjectivePoint>::new(&mut signers, message)
+        .sign()
+        .unwrap();
+    let key_agg = KeyAggContext::<_, Sha256>::new(&pk_list).unwrap();
+    assert!(verify(&signature, &key_agg, message).is_ok());
+
+    // Signing used up the nonces
+    assert_eq!(
+        MuSig::<ProjectivePoint>::new(&mut signers, b"again")
+            .sign()
+            .unwrap_err(),
+        Error::NonceReused
+    );
+
+    // The aggregate key depends on the order of the public keys
+    let mut reversed = pk_list.clone();
+    reversed.reverse();
+    let key_agg = KeyAggContext::<_, Sha256>::new(&reversed).unwrap();
+    assert_eq!(
+        verify(&signature, &key_agg, message),
+        Err(Error::InvalidSignature)
+    );
+}
+
+#[test]
+fn coordinator_blames_bad_partial_signature() {
+    let key_pairs: Vec<_> = (0..3)
+        .map(|_| KeyPair::<ProjectivePoint>::random())
+        .collect();
+    let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+    let key_agg = KeyAggContext::new(&pk_list).unwrap();
+    let message = b"blame";
+    let session_id = SessionId::random();
+    let mut coordinator = Coordinator::<ProjectivePoint>::new(&key_agg, session_id, message);
+
+    let mut sessions = Vec::new();
+    let mut nonce_messages = Vec::new();
+    for (index, key_pair) in key_pairs.into_iter().enumerate() {
+        let (session, nonce_message) =
+            SignerSession::<ProjectivePoint>::new(key_pair, &key_agg, index, session_id, None)
+                .unwrap();
+        sessions.push(session);
+        nonce_messages.push(nonce_message);
+    }
+    for nonce_message in &nonce_messages {
+        coordinator.receive_nonce(nonce_message).unwrap();
+        for session in sessions.iter_mut() {
+            if session.index() != nonce_message.index() {
+                session.receive_nonce(nonce_message).unwrap();
+            }
+        }
+    }
+
+    let partial_signatures: Vec<_> = sessions
+        .into_iter()
+        .map(|session| session.sign(message).unwrap())
+        .collect();
+    // Signer 2 replays signer 0's partial signature as its own
+    let mut replayed = partial_signatures[0].to_bytes();
+    replayed[35] = 2;
+    coordinator
+        .receive_partial_signature(&partial_signatures[0])
+        .unwrap();
+    coordinator
+        .receive_partial_signature(&partial_signatures[1])
+        .unwrap();
+    coordinator
+        .receive_partial_signature(&PartialSignatureMessage::from_bytes(&replayed).unwrap())
+        .unwrap();
+    assert_eq!(
+        coordinator.signature(),
+        Err(Error::InvalidPartialSignatures(vec![2]))
+    );
+}
+
+/// Test that a key listed twice signs once for each of its positions
+#[test]
+fn distributed_signature_with_duplicate_keys() {
+    let repeated = KeyPair::<ProjectivePoint>::random();
+    let key_pairs = vec![repeated.clone(), KeyPair::random(), repeated];
+    let pk_list: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+    let message = b"duplicate";
+
+    let signature = sign_distributed(key_pairs, &pk_list, message).unwrap();
+    let key_agg = KeyAggContext::<_, Sha256>::new(&pk_list).unwrap();
+    assert!(verify(&signature, &key_agg, message).is_ok());
+}
@@ -0,0 +1,59 @@
+#![cfg(all(feature = "p256", feature = "p384"))]
+
+use elliptic_curves::{
+    ecdh::ECDHKdfGroup,
+    ecdsa::{sign_with_nonce, ECDSAGroup, Nonce},
+    KeyPair,
+};
+use sha2::Sha384;
+
+#[test]
+fn ecdsa_p256() {
+    let key_pair = KeyPair::<p256::ProjectivePoint>::random();
+    let signature =
+        <p256::ProjectivePoint as ECDSAGroup>::sign(key_pair.secret_key(), b"message").unwrap();
+    assert!(<p256::ProjectivePoint as ECDSAGroup>::verify(
+        signature,
+        b"message",
+        key_pair.public_key()
+    )
+    .is_ok());
+}
+
+#[test]
+fn ecdsa_p384() {
+    let key_pair = KeyPair::<p384::ProjectivePoint>::random();
+    let signature = sign_with_nonce::<p384::ProjectivePoint, Sha384>(
+        key_pair.secret_key(),
+        b"message",
+        Nonce::Deterministic,
+    )
+    .unwrap();
+    assert!(<p384::ProjectivePoint as ECDSAGroup<Sha384>>::verify(
+        signature,
+        b"message",
+        key_pair.public_key()
+    )
+    .is_ok());
+}
+
+#[test]
+fn ecdh_derive_key_p256() {
+    let key_pair_a = KeyPair::<p256::ProjectivePoint>::random();
+    let key_pair_b = KeyPair::<p256::ProjectivePoint>::random();
+    let key_a = <p256::ProjectivePoint as ECDHKdfGroup>::derive_key(
+        key_pair_a.secret_key(),
+        key_pair_b.public_key(),
+        b"salt",
+        b"info",
+        32,
+    );
+    let key_b = <p256::ProjectivePoint as ECDHKdfGroup>::derive_key(
+        key_pair_b.secret_key(),
+        key_pair_a.public_key(),
+        b"salt",
+        b"info",
+        32,
+    );
+    assert_eq!(key_a, key_b);
+}